@@ -0,0 +1,292 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::lots::normalize_symbol;
+use crate::prices::latest_close;
+use crate::DB;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceAlert {
+    id: Option<i32>,
+    symbol: String,
+    /// One of `above`, `below` or `percent_change`.
+    condition: String,
+    threshold: f64,
+    #[serde(default)]
+    reference_price: Option<f64>,
+    /// Re-arming alerts stay active and fire again after the condition resets.
+    #[serde(default)]
+    rearm: bool,
+    #[serde(default = "default_active")]
+    active: bool,
+    #[serde(default)]
+    last_triggered_at: Option<String>,
+    #[serde(default)]
+    last_triggered_price: Option<f64>,
+    #[serde(default)]
+    created_at: String,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggeredAlert {
+    alert_id: i32,
+    symbol: String,
+    condition: String,
+    threshold: f64,
+    price: f64,
+    message: String,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS price_alerts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            condition TEXT NOT NULL,
+            threshold REAL NOT NULL,
+            reference_price REAL,
+            rearm BOOLEAN DEFAULT 0,
+            active BOOLEAN DEFAULT 1,
+            armed BOOLEAN DEFAULT 1,
+            last_triggered_at TEXT,
+            last_triggered_price REAL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Threshold alerts only fire on a crossing, so they start armed only while the last known
+/// price is still on the near side of the threshold (or no price is known yet).
+fn starts_armed(conn: &Connection, symbol: &str, condition: &str, threshold: f64) -> Result<bool, String> {
+    let last_price = match latest_close(conn, symbol)? {
+        Some((_, close)) => Some(close),
+        None => conn.query_row(
+            "SELECT current_price FROM portfolio WHERE symbol = ? AND current_price > 0",
+            params![symbol],
+            |row| row.get::<_, f64>(0),
+        ).ok(),
+    };
+
+    Ok(match (condition, last_price) {
+        ("above", Some(price)) => price < threshold,
+        ("below", Some(price)) => price > threshold,
+        _ => true,
+    })
+}
+
+/// Checks every active alert for `symbol` against `price` and records the ones that fire.
+pub fn evaluate_alerts(conn: &Connection, symbol: &str, price: f64) -> Result<Vec<TriggeredAlert>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, condition, threshold, reference_price, rearm, armed FROM price_alerts WHERE symbol = ? AND active = 1",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let rows = stmt.query_map(params![symbol], |row| {
+        Ok((
+            row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(2)?,
+            row.get::<_, Option<f64>>(3)?,
+            row.get::<_, bool>(4)?,
+            row.get::<_, bool>(5)?,
+        ))
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut alerts = Vec::new();
+    for row in rows {
+        alerts.push(row.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut triggered = Vec::new();
+
+    for (id, condition, threshold, reference_price, rearm, armed) in alerts {
+        let hit = match condition.as_str() {
+            "above" => price >= threshold,
+            "below" => price <= threshold,
+            "percent_change" => match reference_price {
+                Some(reference) if reference > 0.0 => ((price - reference) / reference * 100.0).abs() >= threshold,
+                // The first quote becomes the baseline for percent moves.
+                _ => {
+                    conn.execute("UPDATE price_alerts SET reference_price = ? WHERE id = ?", params![price, id])
+                        .map_err(|e| format!("Database error: {}", e))?;
+                    false
+                }
+            },
+            _ => false,
+        };
+
+        if !hit {
+            // A re-arming threshold alert becomes live again once the price crosses back.
+            if !armed && condition != "percent_change" {
+                conn.execute("UPDATE price_alerts SET armed = 1 WHERE id = ?", params![id])
+                    .map_err(|e| format!("Database error: {}", e))?;
+            }
+            continue;
+        }
+        if !armed {
+            continue;
+        }
+
+        if !rearm {
+            conn.execute(
+                "UPDATE price_alerts SET active = 0, armed = 0, last_triggered_at = ?, last_triggered_price = ? WHERE id = ?",
+                params![now, price, id],
+            ).map_err(|e| format!("Database error: {}", e))?;
+        } else if condition == "percent_change" {
+            // Percent alerts measure the next move from where this one fired.
+            conn.execute(
+                "UPDATE price_alerts SET reference_price = ?, last_triggered_at = ?, last_triggered_price = ? WHERE id = ?",
+                params![price, now, price, id],
+            ).map_err(|e| format!("Database error: {}", e))?;
+        } else {
+            conn.execute(
+                "UPDATE price_alerts SET armed = 0, last_triggered_at = ?, last_triggered_price = ? WHERE id = ?",
+                params![now, price, id],
+            ).map_err(|e| format!("Database error: {}", e))?;
+        }
+
+        let message = match condition.as_str() {
+            "above" => format!("{} rose to {:.2} (above {:.2})", symbol, price, threshold),
+            "below" => format!("{} fell to {:.2} (below {:.2})", symbol, price, threshold),
+            _ => format!("{} moved {:.2}% to {:.2}", symbol, threshold, price),
+        };
+        triggered.push(TriggeredAlert {
+            alert_id: id,
+            symbol: symbol.to_string(),
+            condition,
+            threshold,
+            price,
+            message,
+        });
+    }
+
+    Ok(triggered)
+}
+
+/// Emits a `price-alert` event and shows a desktop notification for each triggered alert.
+pub fn notify_triggered(app: &AppHandle, triggered: &[TriggeredAlert]) {
+    for alert in triggered {
+        if let Err(e) = app.emit("price-alert", alert.clone()) {
+            eprintln!("Failed to emit price alert: {}", e);
+        }
+        if let Err(e) = app.notification().builder().title("Price alert").body(&alert.message).show() {
+            eprintln!("Failed to show price alert notification: {}", e);
+        }
+    }
+}
+
+/// Drops a symbol's alerts, e.g. when its position is purged from the trash.
+pub fn clear_symbol_alerts(conn: &Connection, symbol: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM price_alerts WHERE symbol = ?", params![symbol])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
+// Price alert commands
+#[tauri::command]
+pub fn get_price_alerts(symbol: Option<String>) -> Result<Vec<PriceAlert>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let symbol = symbol.map(|s| normalize_symbol(&s));
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, condition, threshold, reference_price, rearm, active, last_triggered_at, last_triggered_price, created_at
+         FROM price_alerts WHERE ?1 IS NULL OR symbol = ?1 ORDER BY symbol, id",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let alerts = stmt.query_map(params![symbol], |row| {
+        Ok(PriceAlert {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            condition: row.get(2)?,
+            threshold: row.get(3)?,
+            reference_price: row.get(4)?,
+            rearm: row.get(5)?,
+            active: row.get(6)?,
+            last_triggered_at: row.get(7)?,
+            last_triggered_price: row.get(8)?,
+            created_at: row.get(9)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for alert in alerts {
+        result.push(alert.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn save_price_alert(alert: PriceAlert) -> Result<i32, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let symbol = normalize_symbol(&alert.symbol);
+    if symbol.is_empty() {
+        return Err("Symbol is required".to_string());
+    }
+    if !matches!(alert.condition.as_str(), "above" | "below" | "percent_change") {
+        return Err(format!("Unknown alert condition '{}'", alert.condition));
+    }
+    if alert.threshold <= 0.0 {
+        return Err("Threshold must be greater than zero".to_string());
+    }
+
+    // Percent alerts default to measuring from the position's current price.
+    let reference_price = match alert.reference_price {
+        Some(price) => Some(price),
+        None if alert.condition == "percent_change" => conn.query_row(
+            "SELECT current_price FROM portfolio WHERE symbol = ? AND current_price > 0",
+            params![symbol],
+            |row| row.get::<_, f64>(0),
+        ).ok(),
+        None => None,
+    };
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let armed = starts_armed(conn, &symbol, &alert.condition, alert.threshold)?;
+
+    if let Some(id) = alert.id {
+        // Update existing alert; saving re-arms it unless the price is already past the threshold
+        let updated = conn.execute(
+            "UPDATE price_alerts SET symbol = ?, condition = ?, threshold = ?, reference_price = ?, rearm = ?, active = ?, armed = ? WHERE id = ?",
+            params![symbol, alert.condition, alert.threshold, reference_price, alert.rearm, alert.active, armed, id],
+        ).map_err(|e| format!("Database error: {}", e))?;
+        if updated == 0 {
+            return Err(format!("Price alert {} not found", id));
+        }
+        Ok(id)
+    } else {
+        // Insert new alert
+        conn.execute(
+            "INSERT INTO price_alerts (symbol, condition, threshold, reference_price, rearm, active, armed, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![symbol, alert.condition, alert.threshold, reference_price, alert.rearm, alert.active, armed, now],
+        ).map_err(|e| format!("Database error: {}", e))?;
+        Ok(conn.last_insert_rowid() as i32)
+    }
+}
+
+#[tauri::command]
+pub fn delete_price_alert(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let deleted = conn.execute("DELETE FROM price_alerts WHERE id = ?", params![id])
+        .map_err(|e| format!("Database error: {}", e))?;
+    if deleted == 0 {
+        return Err(format!("Price alert {} not found", id));
+    }
+
+    Ok(())
+}
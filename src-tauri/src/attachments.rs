@@ -0,0 +1,315 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::DB;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteAttachment {
+    id: i32,
+    note_id: i32,
+    file_name: String,
+    mime_type: String,
+    size: i64,
+    /// SHA-256 of the content; identical files share one blob.
+    hash: String,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlobGcReport {
+    blobs_removed: usize,
+    bytes_freed: u64,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blobs (
+            hash TEXT PRIMARY KEY,
+            size INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS note_attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_id INTEGER NOT NULL,
+            blob_hash TEXT NOT NULL,
+            file_name TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_note_attachments_note ON note_attachments(note_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_note_attachments_blob ON note_attachments(blob_hash)", [])?;
+
+    Ok(())
+}
+
+fn blob_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    Ok(dir.join("blobs"))
+}
+
+// Blobs fan out by the first two hex digits so no directory grows too large.
+fn blob_path(root: &Path, hash: &str) -> PathBuf {
+    root.join(&hash[..2]).join(hash)
+}
+
+fn mime_type(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Writes `data` into the store unless a blob with the same hash is already there.
+fn store_blob(conn: &Connection, root: &Path, data: &[u8]) -> Result<String, String> {
+    let hash = format!("{:x}", Sha256::digest(data));
+    let path = blob_path(root, &hash);
+
+    if !path.exists() {
+        let dir = path.parent().ok_or("Invalid blob path")?;
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create blob directory: {}", e))?;
+        // Write beside the final name and rename so a crash never leaves a truncated blob
+        let partial = dir.join(format!("{}.partial", hash));
+        fs::write(&partial, data).map_err(|e| format!("Failed to write blob: {}", e))?;
+        fs::rename(&partial, &path).map_err(|e| format!("Failed to write blob: {}", e))?;
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT OR IGNORE INTO blobs (hash, size, created_at) VALUES (?, ?, ?)",
+        params![hash, data.len() as i64, now],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(hash)
+}
+
+fn load_attachment(conn: &Connection, id: i32) -> Result<NoteAttachment, String> {
+    conn.query_row(
+        "SELECT id, note_id, file_name, mime_type, size, blob_hash, created_at FROM note_attachments WHERE id = ?",
+        params![id],
+        |row| {
+            Ok(NoteAttachment {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                file_name: row.get(2)?,
+                mime_type: row.get(3)?,
+                size: row.get(4)?,
+                hash: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        },
+    ).map_err(|_| format!("Attachment {} not found", id))
+}
+
+fn attach(conn: &Connection, root: &Path, note_id: i32, file_name: &str, data: &[u8]) -> Result<NoteAttachment, String> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM notes WHERE id = ? AND deleted_at IS NULL",
+        params![note_id],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+    if !exists {
+        return Err(format!("Note {} not found", note_id));
+    }
+
+    let file_name = Path::new(file_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+        .ok_or("Attachment needs a file name")?;
+    let hash = store_blob(conn, root, data)?;
+
+    // Pasting the same file into the same note again reuses the attachment
+    let existing: Option<i32> = conn.query_row(
+        "SELECT id FROM note_attachments WHERE note_id = ? AND blob_hash = ? AND file_name = ?",
+        params![note_id, hash, file_name],
+        |row| row.get(0),
+    ).ok();
+    if let Some(id) = existing {
+        return load_attachment(conn, id);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO note_attachments (note_id, blob_hash, file_name, mime_type, size, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![note_id, hash, file_name, mime_type(&file_name), data.len() as i64, now],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    load_attachment(conn, conn.last_insert_rowid() as i32)
+}
+
+/// Deletes blobs no attachment refers to, plus stray files the table doesn't know about.
+pub fn collect_garbage(conn: &Connection, root: &Path) -> Result<BlobGcReport, String> {
+    let mut report = BlobGcReport { blobs_removed: 0, bytes_freed: 0 };
+
+    let mut stmt = conn.prepare("SELECT hash FROM blobs WHERE hash NOT IN (SELECT blob_hash FROM note_attachments)")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let unreferenced: Vec<String> = stmt.query_map([], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?
+        .collect::<SqliteResult<_>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    for hash in unreferenced {
+        let path = blob_path(root, &hash);
+        if let Ok(metadata) = fs::metadata(&path) {
+            fs::remove_file(&path).map_err(|e| format!("Failed to remove blob: {}", e))?;
+            report.bytes_freed += metadata.len();
+        }
+        conn.execute("DELETE FROM blobs WHERE hash = ?", params![hash])
+            .map_err(|e| format!("Database error: {}", e))?;
+        report.blobs_removed += 1;
+    }
+
+    let fanout = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(report),
+    };
+    for dir in fanout.flatten().filter(|entry| entry.path().is_dir()) {
+        for file in fs::read_dir(dir.path()).map_err(|e| format!("Failed to read blob directory: {}", e))?.flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+            let known: bool = conn.query_row("SELECT COUNT(*) > 0 FROM blobs WHERE hash = ?", params![name], |row| row.get(0))
+                .map_err(|e| format!("Database error: {}", e))?;
+            if !known {
+                report.bytes_freed += file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+                fs::remove_file(file.path()).map_err(|e| format!("Failed to remove blob: {}", e))?;
+                report.blobs_removed += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Startup sweep; attachments of notes purged from the trash leave their blobs behind until then.
+pub fn collect_garbage_on_startup(app: &AppHandle) -> Result<(), String> {
+    let root = blob_root(app)?;
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    collect_garbage(conn, &root).map(|_| ())
+}
+
+// Note attachment commands
+#[tauri::command]
+pub fn attach_file_to_note(app: AppHandle, note_id: i32, file_path: String) -> Result<NoteAttachment, String> {
+    let root = blob_root(&app)?;
+    let data = fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    attach(conn, &root, note_id, &file_path, &data)
+}
+
+#[tauri::command]
+pub fn attach_data_to_note(app: AppHandle, note_id: i32, file_name: String, data: Vec<u8>) -> Result<NoteAttachment, String> {
+    let root = blob_root(&app)?;
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    attach(conn, &root, note_id, &file_name, &data)
+}
+
+#[tauri::command]
+pub fn list_note_attachments(note_id: i32) -> Result<Vec<NoteAttachment>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, note_id, file_name, mime_type, size, blob_hash, created_at FROM note_attachments WHERE note_id = ? ORDER BY id",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let attachments = stmt.query_map(params![note_id], |row| {
+        Ok(NoteAttachment {
+            id: row.get(0)?,
+            note_id: row.get(1)?,
+            file_name: row.get(2)?,
+            mime_type: row.get(3)?,
+            size: row.get(4)?,
+            hash: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for attachment in attachments {
+        result.push(attachment.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+/// Copies the blob out under its original file name and opens it with the system's default app.
+#[tauri::command]
+pub fn open_note_attachment(app: AppHandle, id: i32) -> Result<String, String> {
+    let root = blob_root(&app)?;
+    let attachment = {
+        let db_guard = DB.lock().unwrap();
+        let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+        load_attachment(conn, id)?
+    };
+
+    // Blobs have no extension, so the viewer gets a named copy
+    let dir = std::env::temp_dir().join("simplelife-attachments").join(&attachment.hash);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let target = dir.join(&attachment.file_name);
+    fs::copy(blob_path(&root, &attachment.hash), &target)
+        .map_err(|e| format!("Failed to read attachment: {}", e))?;
+
+    let target = target.to_string_lossy().to_string();
+    app.opener().open_path(target.clone(), None::<&str>)
+        .map_err(|e| format!("Failed to open attachment: {}", e))?;
+
+    Ok(target)
+}
+
+#[tauri::command]
+pub fn detach_note_attachment(app: AppHandle, id: i32) -> Result<(), String> {
+    let root = blob_root(&app)?;
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let deleted = conn.execute("DELETE FROM note_attachments WHERE id = ?", params![id])
+        .map_err(|e| format!("Database error: {}", e))?;
+    if deleted == 0 {
+        return Err(format!("Attachment {} not found", id));
+    }
+
+    collect_garbage(conn, &root).map(|_| ())
+}
+
+#[tauri::command]
+pub fn gc_blobs(app: AppHandle) -> Result<BlobGcReport, String> {
+    let root = blob_root(&app)?;
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    collect_garbage(conn, &root)
+}
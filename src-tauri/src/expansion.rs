@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Local, Timelike};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::DB;
+
+/// Parsed snippet body in the VS Code/TextMate syntax.
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    /// `$1`, `${1}` or `${1:placeholder}`; index 0 is the final cursor.
+    TabStop { index: u32, placeholder: Vec<Node> },
+    /// `${1|one,two|}`; expands to the first option.
+    Choice { index: u32, options: Vec<String> },
+    /// `$NAME` or `${NAME:default}`.
+    Variable { name: String, default: Option<Vec<Node>> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TabStop {
+    index: u32,
+    /// `[start, end)` offsets of each occurrence, in UTF-16 code units like JavaScript strings.
+    ranges: Vec<(usize, usize)>,
+    /// Alternatives offered by a choice tab stop.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    options: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExpandedSnippet {
+    text: String,
+    /// Tab stops in visiting order, with `$0` last.
+    tab_stops: Vec<TabStop>,
+    /// Where the cursor ends up: `$0` if present, otherwise the end of the text.
+    cursor: usize,
+    /// Variables that had no value, built-in or default, and expanded to nothing.
+    unresolved: Vec<String>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let start = self.pos;
+        if !self.peek().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        Some(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Parses until the end, or until an unescaped `}` when inside a placeholder.
+    fn nodes(&mut self, nested: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            if nested && c == '}' {
+                break;
+            }
+            if c == '\\' && matches!(self.chars.get(self.pos + 1), Some('$' | '}' | '\\')) {
+                text.push(self.chars[self.pos + 1]);
+                self.pos += 2;
+                continue;
+            }
+            if c == '$' {
+                let start = self.pos;
+                self.pos += 1;
+                match self.dollar() {
+                    Some(node) => {
+                        if !text.is_empty() {
+                            nodes.push(Node::Text(std::mem::take(&mut text)));
+                        }
+                        nodes.push(node);
+                    }
+                    // Not snippet syntax after all, so the `$` is literal
+                    None => {
+                        self.pos = start + 1;
+                        text.push('$');
+                    }
+                }
+                continue;
+            }
+            text.push(c);
+            self.pos += 1;
+        }
+
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        nodes
+    }
+
+    fn dollar(&mut self) -> Option<Node> {
+        if let Some(index) = self.number() {
+            return Some(Node::TabStop { index, placeholder: Vec::new() });
+        }
+        if let Some(name) = self.name() {
+            return Some(Node::Variable { name, default: None });
+        }
+        if !self.eat('{') {
+            return None;
+        }
+
+        if let Some(index) = self.number() {
+            if self.eat('}') {
+                return Some(Node::TabStop { index, placeholder: Vec::new() });
+            }
+            if self.eat(':') {
+                let placeholder = self.nodes(true);
+                return self.eat('}').then_some(Node::TabStop { index, placeholder });
+            }
+            if self.eat('|') {
+                let options = self.choice_options()?;
+                return Some(Node::Choice { index, options });
+            }
+            return None;
+        }
+
+        let name = self.name()?;
+        if self.eat('}') {
+            return Some(Node::Variable { name, default: None });
+        }
+        if self.eat(':') {
+            let default = self.nodes(true);
+            return self.eat('}').then_some(Node::Variable { name, default: Some(default) });
+        }
+        None
+    }
+
+    /// Reads `a,b\,c|}` after the opening `|`.
+    fn choice_options(&mut self) -> Option<Vec<String>> {
+        let mut options = Vec::new();
+        let mut current = String::new();
+        loop {
+            let c = self.peek()?;
+            self.pos += 1;
+            match c {
+                '\\' if matches!(self.peek(), Some(',' | '|' | '\\' | '$' | '}')) => {
+                    current.push(self.peek()?);
+                    self.pos += 1;
+                }
+                ',' => options.push(std::mem::take(&mut current)),
+                '|' if self.eat('}') => {
+                    options.push(current);
+                    return Some(options);
+                }
+                _ => current.push(c),
+            }
+        }
+    }
+}
+
+/// Parses a snippet body; malformed `$` sequences are kept as literal text.
+fn parse(source: &str) -> Vec<Node> {
+    Parser { chars: source.chars().collect(), pos: 0 }.nodes(false)
+}
+
+fn contains_syntax(nodes: &[Node]) -> bool {
+    nodes.iter().any(|node| !matches!(node, Node::Text(_)))
+}
+
+/// Whether a snippet uses tab stops or variables, so expanding it changes anything.
+pub fn is_expandable(code: &str) -> bool {
+    contains_syntax(&parse(code))
+}
+
+/// Resolves the built-in variables VS Code knows, evaluated at expansion time.
+fn builtin_variable(name: &str) -> Option<String> {
+    let now = Local::now();
+    Some(match name {
+        "CURRENT_YEAR" => now.year().to_string(),
+        "CURRENT_YEAR_SHORT" => format!("{:02}", now.year() % 100),
+        "CURRENT_MONTH" => format!("{:02}", now.month()),
+        "CURRENT_MONTH_NAME" => now.format("%B").to_string(),
+        "CURRENT_MONTH_NAME_SHORT" => now.format("%b").to_string(),
+        "CURRENT_DATE" => format!("{:02}", now.day()),
+        "CURRENT_DAY_NAME" => now.format("%A").to_string(),
+        "CURRENT_DAY_NAME_SHORT" => now.format("%a").to_string(),
+        "CURRENT_HOUR" => format!("{:02}", now.hour()),
+        "CURRENT_MINUTE" => format!("{:02}", now.minute()),
+        "CURRENT_SECOND" => format!("{:02}", now.second()),
+        "CURRENT_SECONDS_UNIX" => now.timestamp().to_string(),
+        "CURRENT_TIMEZONE_OFFSET" => now.format("%:z").to_string(),
+        "CLIPBOARD" => arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()).ok()?,
+        "UUID" => uuid::Uuid::new_v4().to_string(),
+        "RANDOM" => format!("{:06}", uuid::Uuid::new_v4().as_u128() % 1_000_000),
+        "RANDOM_HEX" => format!("{:06x}", uuid::Uuid::new_v4().as_u128() & 0xff_ffff),
+        _ => return None,
+    })
+}
+
+struct Expander<'a> {
+    values: &'a HashMap<String, String>,
+    /// First placeholder given for each tab stop, mirrored into its bare occurrences.
+    placeholders: HashMap<u32, Vec<Node>>,
+    /// Tab stops whose placeholder is being expanded, so `${1:a $1}` can't recurse forever.
+    expanding: Vec<u32>,
+    text: String,
+    utf16_len: usize,
+    tab_stops: Vec<TabStop>,
+    unresolved: Vec<String>,
+}
+
+impl Expander<'_> {
+    fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.utf16_len += text.encode_utf16().count();
+    }
+
+    fn record(&mut self, index: u32, range: (usize, usize), options: &[String]) {
+        match self.tab_stops.iter_mut().find(|stop| stop.index == index) {
+            Some(stop) => stop.ranges.push(range),
+            None => self.tab_stops.push(TabStop { index, ranges: vec![range], options: options.to_vec() }),
+        }
+    }
+
+    fn expand(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.push(text),
+                Node::TabStop { index, placeholder } => {
+                    let start = self.utf16_len;
+                    match self.values.get(&index.to_string()) {
+                        Some(value) => self.push(value),
+                        // A tab stop inside its own placeholder stays empty
+                        None if self.expanding.contains(index) => {}
+                        None => {
+                            let nodes = if placeholder.is_empty() {
+                                self.placeholders.get(index).cloned().unwrap_or_default()
+                            } else {
+                                placeholder.clone()
+                            };
+                            self.expanding.push(*index);
+                            self.expand(&nodes);
+                            self.expanding.pop();
+                        }
+                    }
+                    self.record(*index, (start, self.utf16_len), &[]);
+                }
+                Node::Choice { index, options } => {
+                    let start = self.utf16_len;
+                    let value = self.values.get(&index.to_string()).or(options.first()).cloned().unwrap_or_default();
+                    self.push(&value);
+                    self.record(*index, (start, self.utf16_len), options);
+                }
+                Node::Variable { name, default } => {
+                    if let Some(value) = self.values.get(name).cloned().or_else(|| builtin_variable(name)) {
+                        self.push(&value);
+                    } else if let Some(default) = default {
+                        self.expand(default);
+                    } else if !self.unresolved.contains(name) {
+                        self.unresolved.push(name.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn collect_placeholders(nodes: &[Node], placeholders: &mut HashMap<u32, Vec<Node>>) {
+    for node in nodes {
+        match node {
+            Node::TabStop { index, placeholder } if !placeholder.is_empty() => {
+                placeholders.entry(*index).or_insert_with(|| placeholder.clone());
+                collect_placeholders(placeholder, placeholders);
+            }
+            Node::Choice { index, options } => {
+                let first = options.first().map(|option| Node::Text(option.clone()));
+                placeholders.entry(*index).or_insert_with(|| first.into_iter().collect());
+            }
+            Node::Variable { default: Some(default), .. } => collect_placeholders(default, placeholders),
+            _ => {}
+        }
+    }
+}
+
+/// Expands tab stops and variables. `values` fills tab stops by number (`"1"`) and variables by
+/// name, taking precedence over placeholders and built-ins.
+pub fn expand(code: &str, values: &HashMap<String, String>) -> ExpandedSnippet {
+    let nodes = parse(code);
+    let mut placeholders = HashMap::new();
+    collect_placeholders(&nodes, &mut placeholders);
+
+    let mut expander = Expander {
+        values,
+        placeholders,
+        expanding: Vec::new(),
+        text: String::new(),
+        utf16_len: 0,
+        tab_stops: Vec::new(),
+        unresolved: Vec::new(),
+    };
+    expander.expand(&nodes);
+
+    let mut tab_stops = expander.tab_stops;
+    // Visit $1, $2, ... in order and finish on $0
+    tab_stops.sort_by_key(|stop| if stop.index == 0 { u32::MAX } else { stop.index });
+    let cursor = tab_stops.iter()
+        .find(|stop| stop.index == 0)
+        .and_then(|stop| stop.ranges.first())
+        .map(|range| range.0)
+        .unwrap_or(expander.utf16_len);
+
+    ExpandedSnippet {
+        text: expander.text,
+        tab_stops,
+        cursor,
+        unresolved: expander.unresolved,
+    }
+}
+
+// Snippet expansion commands
+#[tauri::command]
+pub fn expand_snippet(id: i32, values: Option<HashMap<String, String>>) -> Result<ExpandedSnippet, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let code: String = conn.query_row(
+        "SELECT code FROM code_snippets WHERE id = ? AND deleted_at IS NULL",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Code snippet {} not found", id))?;
+
+    Ok(expand(&code, &values.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(code: &str) -> ExpandedSnippet {
+        expand(code, &HashMap::new())
+    }
+
+    fn ranges(expanded: &ExpandedSnippet, index: u32) -> Vec<(usize, usize)> {
+        expanded.tab_stops.iter().find(|stop| stop.index == index).map(|stop| stop.ranges.clone()).unwrap_or_default()
+    }
+
+    #[test]
+    fn bare_tab_stops_mirror_the_placeholder() {
+        let expanded = run("${1:name} = $1;$0");
+        assert_eq!(expanded.text, "name = name;");
+        assert_eq!(ranges(&expanded, 1), vec![(0, 4), (7, 11)]);
+        assert_eq!(expanded.cursor, 12);
+    }
+
+    #[test]
+    fn values_override_placeholders() {
+        let values = HashMap::from([("1".to_string(), "x".to_string()), ("USER".to_string(), "ann".to_string())]);
+        let expanded = expand("${1:name}=$1 ${USER:nobody} $MISSING", &values);
+        assert_eq!(expanded.text, "x=x ann ");
+        assert_eq!(expanded.unresolved, vec!["MISSING".to_string()]);
+    }
+
+    #[test]
+    fn nested_placeholders_record_both_stops() {
+        let expanded = run("${1:outer ${2:inner}} $2");
+        assert_eq!(expanded.text, "outer inner inner");
+        assert_eq!(ranges(&expanded, 1), vec![(0, 11)]);
+        assert_eq!(ranges(&expanded, 2), vec![(6, 11), (12, 17)]);
+        assert_eq!(expanded.cursor, 17);
+    }
+
+    #[test]
+    fn choices_expand_to_the_first_option() {
+        let expanded = run("${1|get,post\\,put|} $1");
+        assert_eq!(expanded.text, "get get");
+        assert_eq!(expanded.tab_stops[0].options, vec!["get".to_string(), "post,put".to_string()]);
+    }
+
+    #[test]
+    fn ranges_count_utf16_code_units() {
+        let expanded = run("é😀 ${1:ü} $0");
+        assert_eq!(expanded.text, "é😀 ü ");
+        assert_eq!(ranges(&expanded, 1), vec![(4, 5)]);
+        assert_eq!(expanded.cursor, 6);
+    }
+
+    #[test]
+    fn self_referencing_placeholders_terminate() {
+        let expanded = run("${1:a $1}");
+        assert_eq!(expanded.text, "a ");
+        assert_eq!(ranges(&expanded, 1), vec![(2, 2), (0, 2)]);
+
+        let expanded = run("${1:$2} ${2:$1}");
+        assert_eq!(expanded.text, " ");
+    }
+
+    #[test]
+    fn malformed_syntax_stays_literal() {
+        assert_eq!(run("cost: $ 5 ${oops \\$1").text, "cost: $ 5 ${oops $1");
+        assert!(!is_expandable("price is $ 5"));
+        assert!(is_expandable("hello $1"));
+    }
+}
@@ -0,0 +1,211 @@
+use lazy_static::lazy_static;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use syntect::highlighting::ThemeSet;
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use crate::DB;
+
+/// syntect themes matching the app's light and dark modes.
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+/// File extensions mapped to snippet language ids.
+const EXTENSIONS: [(&str, &[&str]); 18] = [
+    ("rust", &["rs"]),
+    ("python", &["py", "pyw"]),
+    ("javascript", &["js", "mjs", "cjs", "jsx"]),
+    ("typescript", &["ts", "tsx", "mts"]),
+    ("go", &["go"]),
+    ("java", &["java"]),
+    ("csharp", &["cs"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hh", "h", "c"]),
+    ("php", &["php"]),
+    ("ruby", &["rb", "rake"]),
+    ("sql", &["sql"]),
+    ("html", &["html", "htm"]),
+    ("css", &["css", "scss"]),
+    ("bash", &["sh", "bash", "zsh"]),
+    ("json", &["json"]),
+    ("yaml", &["yml", "yaml"]),
+    ("markdown", &["md", "markdown"]),
+    ("text", &["txt"]),
+];
+
+/// Interpreters named in a shebang line.
+const INTERPRETERS: [(&str, &[&str]); 5] = [
+    ("python", &["python", "python3", "python2"]),
+    ("javascript", &["node", "deno", "bun"]),
+    ("bash", &["sh", "bash", "zsh", "dash", "ksh"]),
+    ("ruby", &["ruby"]),
+    ("php", &["php"]),
+];
+
+/// Tell-tale fragments per language with their weight. Ties go to the earlier language.
+const KEYWORDS: [(&str, &[(&str, i32)]); 14] = [
+    ("php", &[("<?php", 10), ("$this->", 3), ("echo ", 1), ("=> $", 2)]),
+    ("rust", &[("fn ", 2), ("let mut ", 3), ("impl ", 3), ("pub fn ", 3), ("use std::", 4), ("println!", 4), ("-> ", 1), ("&str", 3), ("Vec<", 2), ("Option<", 2)]),
+    ("typescript", &[("interface ", 3), (": string", 3), (": number", 3), (": boolean", 3), ("export type ", 3), ("as const", 3), ("readonly ", 2), ("const ", 1), ("=> ", 1)]),
+    ("javascript", &[("function ", 2), ("const ", 1), ("let ", 1), ("=> ", 1), ("console.log", 3), ("require(", 3), ("document.", 3), ("module.exports", 4), ("export default", 2)]),
+    ("python", &[("def ", 2), ("import ", 1), ("from ", 1), ("self.", 2), ("elif ", 4), ("print(", 2), ("__init__", 4), ("None", 1), ("True", 1), ("):\n", 2)]),
+    ("go", &[("package ", 4), ("func ", 3), (":= ", 3), ("fmt.", 4), ("import (", 4), ("go func", 4), ("err != nil", 4)]),
+    ("java", &[("public class ", 3), ("public static void main", 5), ("System.out.", 5), ("private ", 1), ("import java.", 5), ("@Override", 3)]),
+    ("csharp", &[("using System", 5), ("namespace ", 2), ("Console.Write", 5), ("public class ", 2), ("{ get; set; }", 5), ("var ", 1)]),
+    ("cpp", &[("#include", 5), ("std::", 3), ("cout <<", 4), ("int main(", 3), ("template<", 3), ("nullptr", 3)]),
+    ("ruby", &[("def ", 1), ("end\n", 2), ("puts ", 3), ("require '", 3), ("do |", 4), ("attr_accessor", 5), (".each ", 2)]),
+    ("bash", &[("echo ", 1), ("fi\n", 3), ("then\n", 2), ("; then", 3), ("$1", 2), ("export ", 2), ("done", 2), ("if [", 3), ("sudo ", 2), ("#!/", 1)]),
+    ("html", &[("<!DOCTYPE", 5), ("<html", 5), ("<div", 3), ("</", 1), ("<body", 4), ("class=\"", 2), ("<script", 2)]),
+    ("css", &[("color:", 2), ("margin:", 2), ("padding:", 2), ("px;", 2), ("display:", 2), ("@media", 4), ("font-size:", 2)]),
+    ("markdown", &[("# ", 1), ("## ", 2), ("```", 3), ("](", 3), ("- [ ]", 3), ("**", 1)]),
+];
+
+/// SQL keywords, matched case-insensitively.
+const SQL_KEYWORDS: [(&str, i32); 8] = [
+    ("SELECT ", 2), (" FROM ", 2), (" WHERE ", 2), ("INSERT INTO", 4), ("CREATE TABLE", 5), ("UPDATE ", 1), (" JOIN ", 2), ("GROUP BY", 3),
+];
+
+lazy_static! {
+    static ref SYNTAXES: SyntaxSet = SyntaxSet::load_defaults_newlines();
+    static ref THEMES: ThemeSet = ThemeSet::load_defaults();
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderedSnippet {
+    id: i32,
+    language: String,
+    theme: String,
+    html: String,
+}
+
+/// Language id for a file name's extension, if it has a known one.
+pub fn from_extension(title: &str) -> Option<&'static str> {
+    let (_, extension) = title.trim().rsplit_once('.')?;
+    let extension = extension.to_lowercase();
+    EXTENSIONS.iter()
+        .find(|(_, extensions)| extensions.contains(&extension.as_str()))
+        .map(|(language, _)| *language)
+}
+
+/// The usual file extension for a language id; `txt` when there isn't one.
+pub fn extension_for(language: &str) -> &'static str {
+    EXTENSIONS.iter()
+        .find(|(name, _)| *name == language)
+        .and_then(|(_, extensions)| extensions.first())
+        .copied()
+        .unwrap_or("txt")
+}
+
+fn from_shebang(code: &str) -> Option<&'static str> {
+    let line = code.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+    INTERPRETERS.iter()
+        .find(|(_, names)| names.contains(&program) || names.iter().any(|name| program.starts_with(name) && program[name.len()..].chars().all(|c| c.is_ascii_digit() || c == '.')))
+        .map(|(language, _)| *language)
+}
+
+fn looks_like_yaml(code: &str) -> bool {
+    let lines: Vec<&str> = code.lines().map(str::trim_end).filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#')).collect();
+    if lines.is_empty() || code.contains('{') || code.contains(';') {
+        return false;
+    }
+    let mapping = lines.iter().filter(|line| {
+        let line = line.trim_start().trim_start_matches("- ");
+        line.split_once(':').is_some_and(|(key, rest)| {
+            !key.is_empty() && !key.contains(' ') && (rest.is_empty() || rest.starts_with(' '))
+        })
+    }).count();
+    mapping * 2 > lines.len() || code.starts_with("---\n")
+}
+
+/// Best guess at a snippet's language from its title's extension, a shebang, or its contents.
+pub fn detect_language(title: &str, code: &str) -> String {
+    if let Some(language) = from_extension(title).or_else(|| from_shebang(code)) {
+        return language.to_string();
+    }
+
+    let trimmed = code.trim();
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return "json".to_string();
+    }
+
+    let mut best = ("text", 0);
+    for (language, keywords) in KEYWORDS {
+        let score: i32 = keywords.iter().filter(|(keyword, _)| code.contains(keyword)).map(|(_, weight)| weight).sum();
+        if score > best.1 {
+            best = (language, score);
+        }
+    }
+    let upper = code.to_uppercase();
+    let sql: i32 = SQL_KEYWORDS.iter().filter(|(keyword, _)| upper.contains(keyword)).map(|(_, weight)| weight).sum();
+    if sql > best.1 {
+        best = ("sql", sql);
+    }
+
+    if best.1 >= 3 {
+        best.0.to_string()
+    } else if looks_like_yaml(code) {
+        "yaml".to_string()
+    } else {
+        "text".to_string()
+    }
+}
+
+/// Whether a saved language asks for detection. An explicit `text` is a real choice and sticks.
+pub fn needs_detection(language: &str) -> bool {
+    matches!(language.trim().to_lowercase().as_str(), "" | "auto")
+}
+
+fn syntax_for(language: &str) -> &'static syntect::parsing::SyntaxReference {
+    // Language ids that don't match a syntect token; TypeScript isn't bundled, so it borrows JavaScript
+    let token = match language {
+        "javascript" | "typescript" => "js",
+        "python" => "py",
+        "csharp" => "cs",
+        "ruby" => "rb",
+        "rust" => "rs",
+        "bash" => "sh",
+        "markdown" => "md",
+        other => other,
+    };
+    SYNTAXES.find_syntax_by_token(token)
+        .unwrap_or_else(|| SYNTAXES.find_syntax_plain_text())
+}
+
+pub fn highlight(code: &str, language: &str, dark: bool) -> Result<String, String> {
+    let theme = &THEMES.themes[if dark { DARK_THEME } else { LIGHT_THEME }];
+    highlighted_html_for_string(code, &SYNTAXES, syntax_for(language), theme)
+        .map_err(|e| format!("Highlighting error: {}", e))
+}
+
+// Snippet rendering commands
+/// Highlights a snippet for the given theme (`light` or `dark`), defaulting to the theme in settings.
+#[tauri::command]
+pub fn render_snippet_html(id: i32, theme: Option<String>) -> Result<RenderedSnippet, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let (code, language): (String, String) = conn.query_row(
+        "SELECT code, COALESCE(language, 'text') FROM code_snippets WHERE id = ? AND deleted_at IS NULL",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|_| format!("Code snippet {} not found", id))?;
+
+    let theme = match theme {
+        Some(theme) => theme,
+        None => conn.query_row("SELECT COALESCE(theme, 'light') FROM settings LIMIT 1", [], |row| row.get(0))
+            .unwrap_or_else(|_| "light".to_string()),
+    };
+    let dark = theme == "dark";
+
+    Ok(RenderedSnippet {
+        id,
+        html: highlight(&code, &language, dark)?,
+        language,
+        theme: if dark { "dark" } else { "light" }.to_string(),
+    })
+}
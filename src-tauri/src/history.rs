@@ -0,0 +1,459 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::DB;
+
+/// Commands too trivial to be worth a template.
+const TRIVIAL_COMMANDS: [&str; 10] = ["ls", "ll", "la", "cd", "pwd", "clear", "exit", "history", "fg", "bg"];
+
+/// First-word prefixes mapped to template categories; anything else lands in `general`.
+const CATEGORY_RULES: [(&str, &[&str]); 9] = [
+    ("git", &["git", "gh", "tig"]),
+    ("docker", &["docker", "docker-compose", "podman"]),
+    ("npm", &["npm", "npx", "yarn", "pnpm", "node", "bun"]),
+    ("kubernetes", &["kubectl", "helm", "minikube", "kind", "k9s"]),
+    ("database", &["psql", "mysql", "sqlite3", "redis-cli", "mongosh", "pg_dump"]),
+    ("rust", &["cargo", "rustup", "rustc"]),
+    ("python", &["python", "python3", "pip", "pip3", "poetry", "uv"]),
+    ("network", &["ssh", "scp", "rsync", "curl", "wget", "ping", "dig"]),
+    ("system", &["systemctl", "journalctl", "apt", "brew", "df", "du", "ps", "kill", "top", "chmod", "chown", "tar", "find"]),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    command: String,
+    /// Unix seconds, when the shell recorded one.
+    timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistorySuggestion {
+    command: String,
+    title: String,
+    category: String,
+    count: usize,
+    last_used: Option<String>,
+    /// Shells the command was found in.
+    shells: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryTemplate {
+    command: String,
+    title: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    tags: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryImportResult {
+    imported: Vec<i32>,
+    /// Commands skipped because a template with the same command already exists.
+    duplicates: Vec<String>,
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// The history files each shell keeps by default: `(shell, path)`.
+fn default_history_files() -> Vec<(String, PathBuf)> {
+    let Some(home) = home_dir() else {
+        return Vec::new();
+    };
+    let fish_data = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local").join("share"));
+
+    vec![
+        ("bash".to_string(), home.join(".bash_history")),
+        ("zsh".to_string(), home.join(".zsh_history")),
+        ("fish".to_string(), fish_data.join("fish").join("fish_history")),
+    ]
+}
+
+fn detect_shell(path: &Path, contents: &str) -> String {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if name.contains("fish") || contents.lines().next().is_some_and(|line| line.starts_with("- cmd: ")) {
+        "fish".to_string()
+    } else if name.contains("zsh") || contents.lines().next().is_some_and(|line| parse_zsh_extended(line).is_some()) {
+        "zsh".to_string()
+    } else {
+        "bash".to_string()
+    }
+}
+
+// zsh stores non-ASCII bytes "metafied": 0x83 followed by the byte xor 32.
+fn unmetafy(bytes: &[u8]) -> String {
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+    while let Some(&byte) = iter.next() {
+        if byte == 0x83 {
+            if let Some(&next) = iter.next() {
+                result.push(next ^ 32);
+            }
+        } else {
+            result.push(byte);
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Splits `: 1700000000:0;git status` into its timestamp and command.
+fn parse_zsh_extended(line: &str) -> Option<(i64, &str)> {
+    let rest = line.strip_prefix(": ")?;
+    let (meta, command) = rest.split_once(';')?;
+    let (timestamp, duration) = meta.split_once(':')?;
+    if !duration.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some((timestamp.trim().parse().ok()?, command))
+}
+
+/// Bash history; `#1700000000` lines (written when HISTTIMEFORMAT is set) stamp the next command.
+pub fn parse_bash_history(contents: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = None;
+
+    for line in contents.lines() {
+        if let Some(stamp) = line.strip_prefix('#').and_then(|rest| rest.trim().parse::<i64>().ok()) {
+            timestamp = Some(stamp);
+            continue;
+        }
+        if !line.trim().is_empty() {
+            entries.push(HistoryEntry { command: line.trim().to_string(), timestamp: timestamp.take() });
+        }
+    }
+
+    entries
+}
+
+/// Zsh history, plain or with EXTENDED_HISTORY timestamps; a trailing backslash continues the command.
+pub fn parse_zsh_history(contents: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut continuing = false;
+
+    for line in contents.lines() {
+        if continuing {
+            if let Some(entry) = entries.last_mut() {
+                entry.command.pop();
+                entry.command.push('\n');
+                entry.command.push_str(line);
+            }
+        } else {
+            let (timestamp, command) = match parse_zsh_extended(line) {
+                Some((timestamp, command)) => (Some(timestamp), command),
+                None => (None, line),
+            };
+            entries.push(HistoryEntry { command: command.to_string(), timestamp });
+        }
+        continuing = line.ends_with('\\');
+    }
+
+    entries.retain(|entry| !entry.command.trim().is_empty());
+    for entry in &mut entries {
+        entry.command = entry.command.trim().to_string();
+    }
+    entries
+}
+
+/// Fish history, a YAML-like list of `- cmd:` entries with a `when:` timestamp.
+pub fn parse_fish_history(contents: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+
+    for line in contents.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            let command = command.replace("\\n", "\n").replace("\\\\", "\\");
+            entries.push(HistoryEntry { command: command.trim().to_string(), timestamp: None });
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ") {
+            if let Some(entry) = entries.last_mut() {
+                entry.timestamp = when.trim().parse().ok();
+            }
+        }
+    }
+
+    entries.retain(|entry| !entry.command.is_empty());
+    entries
+}
+
+fn read_history_file(shell: &str, path: &Path) -> Result<Vec<HistoryEntry>, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let contents = if shell == "zsh" { unmetafy(&bytes) } else { String::from_utf8_lossy(&bytes).into_owned() };
+
+    Ok(match shell {
+        "zsh" => parse_zsh_history(&contents),
+        "fish" => parse_fish_history(&contents),
+        _ => parse_bash_history(&contents),
+    })
+}
+
+/// Collapses runs of whitespace so `git  status` and `git status` count as one command.
+pub fn normalize_command(command: &str) -> String {
+    command.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The program a command runs, skipping `sudo` and leading `VAR=value` assignments.
+fn program(command: &str) -> &str {
+    command.split_whitespace()
+        .find(|word| *word != "sudo" && (!word.contains('=') || word.starts_with('-')))
+        .map(|word| word.rsplit('/').next().unwrap_or(word))
+        .unwrap_or_default()
+}
+
+pub fn infer_category(command: &str) -> String {
+    let program = program(command);
+    CATEGORY_RULES.iter()
+        .find(|(_, programs)| programs.contains(&program))
+        .map(|(category, _)| category.to_string())
+        .unwrap_or_else(|| "general".to_string())
+}
+
+fn suggest_title(command: &str) -> String {
+    let words: Vec<&str> = command.split_whitespace()
+        .filter(|word| *word != "sudo")
+        .take_while(|word| !word.starts_with('-'))
+        .take(3)
+        .collect();
+    let title = if words.is_empty() { command.to_string() } else { words.join(" ") };
+
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).take(60).collect(),
+        None => title,
+    }
+}
+
+fn is_trivial(command: &str) -> bool {
+    let mut words = command.split_whitespace();
+    let first = words.next().unwrap_or_default();
+    command.len() < 4 || (TRIVIAL_COMMANDS.contains(&first) && words.count() <= 1)
+}
+
+fn existing_commands(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn.prepare("SELECT command FROM command_templates WHERE deleted_at IS NULL")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let commands = stmt.query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = HashSet::new();
+    for command in commands {
+        result.insert(normalize_command(&command.map_err(|e| format!("Database error: {}", e))?));
+    }
+
+    Ok(result)
+}
+
+/// Ranks history entries by how often they ran, most recent first among ties, leaving out
+/// trivial commands and ones already saved as templates.
+pub fn rank_history(entries: &[(String, HistoryEntry)], existing: &HashSet<String>, min_count: usize) -> Vec<HistorySuggestion> {
+    let mut ranked: HashMap<String, HistorySuggestion> = HashMap::new();
+    let mut latest: HashMap<String, i64> = HashMap::new();
+
+    for (shell, entry) in entries {
+        let command = normalize_command(&entry.command);
+        if is_trivial(&command) || existing.contains(&command) {
+            continue;
+        }
+
+        let suggestion = ranked.entry(command.clone()).or_insert_with(|| HistorySuggestion {
+            title: suggest_title(&command),
+            category: infer_category(&command),
+            command: command.clone(),
+            count: 0,
+            last_used: None,
+            shells: Vec::new(),
+        });
+        suggestion.count += 1;
+        if !suggestion.shells.contains(shell) {
+            suggestion.shells.push(shell.clone());
+        }
+        if let Some(timestamp) = entry.timestamp {
+            let newest = latest.entry(command).or_insert(timestamp);
+            *newest = (*newest).max(timestamp);
+        }
+    }
+
+    let mut result: Vec<HistorySuggestion> = ranked.into_values()
+        .filter(|suggestion| suggestion.count >= min_count.max(1))
+        .map(|mut suggestion| {
+            suggestion.last_used = latest.get(&suggestion.command)
+                .and_then(|timestamp| chrono::DateTime::from_timestamp(*timestamp, 0))
+                .map(|when| when.to_rfc3339());
+            suggestion
+        })
+        .collect();
+    result.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| b.last_used.cmp(&a.last_used)).then_with(|| a.command.cmp(&b.command)));
+
+    result
+}
+
+// Shell history import commands
+/// Suggests templates from shell history. Without `files` the default bash, zsh and fish
+/// history locations are read; missing files are skipped.
+#[tauri::command]
+pub fn preview_history_import(files: Option<Vec<String>>, min_count: Option<usize>, limit: Option<usize>) -> Result<Vec<HistorySuggestion>, String> {
+    let sources: Vec<(Option<String>, PathBuf)> = match files {
+        Some(files) => files.into_iter().map(|file| (None, PathBuf::from(file))).collect(),
+        None => default_history_files().into_iter()
+            .filter(|(_, path)| path.is_file())
+            .map(|(shell, path)| (Some(shell), path))
+            .collect(),
+    };
+
+    let mut entries = Vec::new();
+    for (shell, path) in sources {
+        let shell = match shell {
+            Some(shell) => shell,
+            None => {
+                let sample = std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                detect_shell(&path, &String::from_utf8_lossy(&sample[..sample.len().min(4096)]))
+            }
+        };
+        for entry in read_history_file(&shell, &path)? {
+            entries.push((shell.clone(), entry));
+        }
+    }
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut suggestions = rank_history(&entries, &existing_commands(conn)?, min_count.unwrap_or(2));
+    suggestions.truncate(limit.unwrap_or(50));
+    Ok(suggestions)
+}
+
+/// Saves the chosen suggestions as command templates, skipping any that already exist.
+#[tauri::command]
+pub fn commit_history_import(templates: Vec<HistoryTemplate>) -> Result<HistoryImportResult, String> {
+    let mut existing = {
+        let db_guard = DB.lock().unwrap();
+        let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+        existing_commands(conn)?
+    };
+
+    let mut result = HistoryImportResult { imported: Vec::new(), duplicates: Vec::new() };
+    for template in templates {
+        let command = template.command.trim().to_string();
+        if !existing.insert(normalize_command(&command)) {
+            result.duplicates.push(command);
+            continue;
+        }
+
+        let id = crate::save_command_template(crate::CommandTemplate {
+            id: None,
+            title: template.title,
+            description: template.description.unwrap_or_default(),
+            category: template.category.unwrap_or_else(|| infer_category(&command)),
+            command,
+            tags: template.tags.unwrap_or_default(),
+            created_at: String::new(),
+            use_count: 0,
+            last_used_at: None,
+            frecency: 0.0,
+        })?;
+        result.imported.push(id);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<(&str, Option<i64>)> {
+        entries.iter().map(|entry| (entry.command.as_str(), entry.timestamp)).collect()
+    }
+
+    fn entry(command: &str, timestamp: Option<i64>) -> HistoryEntry {
+        HistoryEntry { command: command.to_string(), timestamp }
+    }
+
+    #[test]
+    fn bash_timestamps_stamp_only_the_next_command() {
+        let entries = parse_bash_history("ls -la\n#1700000000\n  git status  \n\ncargo build\n");
+        assert_eq!(commands(&entries), [("ls -la", None), ("git status", Some(1700000000)), ("cargo build", None)]);
+    }
+
+    #[test]
+    fn zsh_reads_extended_lines_and_continuations() {
+        let contents = ": 1700000000:0;git status\nplain command\n: 1700000100:3;echo one \\\ntwo\n: not:extended;x\n: 1700000200:0;   \n";
+        let entries = parse_zsh_history(contents);
+        assert_eq!(commands(&entries), [
+            ("git status", Some(1700000000)),
+            ("plain command", None),
+            ("echo one \ntwo", Some(1700000100)),
+            (": not:extended;x", None),
+        ]);
+    }
+
+    #[test]
+    fn zsh_metafied_bytes_are_restored() {
+        // "é" is 0xc3 0xa9, each written as 0x83 followed by the byte xor 32
+        assert_eq!(unmetafy(b"echo \x83\xe3\x83\x89"), "echo é");
+        assert_eq!(unmetafy(b"trailing \x83"), "trailing ");
+    }
+
+    #[test]
+    fn fish_entries_take_their_when_and_unescape() {
+        let contents = "- cmd: echo a\\nb\n  when: 1700000000\n- cmd: ls C:\\\\tmp\n- cmd: \n  when: 1700000100\n- cmd: git push\n  when: soon\n  paths:\n    - x\n";
+        let entries = parse_fish_history(contents);
+        assert_eq!(commands(&entries), [("echo a\nb", Some(1700000000)), ("ls C:\\tmp", None), ("git push", None)]);
+    }
+
+    #[test]
+    fn shell_is_detected_from_the_name_or_first_line() {
+        assert_eq!(detect_shell(Path::new("fish_history"), "anything"), "fish");
+        assert_eq!(detect_shell(Path::new("history.txt"), "- cmd: ls\n  when: 1\n"), "fish");
+        assert_eq!(detect_shell(Path::new(".zsh_history"), "ls\n"), "zsh");
+        assert_eq!(detect_shell(Path::new("history.txt"), ": 1700000000:0;ls\n"), "zsh");
+        assert_eq!(detect_shell(Path::new(".bash_history"), "#1700000000\nls\n"), "bash");
+    }
+
+    #[test]
+    fn categories_skip_sudo_and_assignments() {
+        assert_eq!(infer_category("sudo systemctl restart nginx"), "system");
+        assert_eq!(infer_category("RUST_LOG=debug cargo run"), "rust");
+        assert_eq!(infer_category("/usr/local/bin/docker ps"), "docker");
+        assert_eq!(infer_category("make build"), "general");
+    }
+
+    #[test]
+    fn ranking_counts_normalized_commands_and_drops_trivial_or_saved_ones() {
+        let entries: Vec<(String, HistoryEntry)> = vec![
+            ("bash".to_string(), entry("git  status", Some(1700000000))),
+            ("zsh".to_string(), entry("git status", Some(1700000500))),
+            ("bash".to_string(), entry("cargo test --workspace", None)),
+            ("bash".to_string(), entry("cargo test --workspace", Some(1700000100))),
+            ("bash".to_string(), entry("docker ps", None)),
+            ("bash".to_string(), entry("docker ps", None)),
+            ("bash".to_string(), entry("ls", None)),
+            ("bash".to_string(), entry("ls", None)),
+            ("bash".to_string(), entry("npm run dev", None)),
+        ];
+        let existing: HashSet<String> = ["docker ps".to_string()].into();
+
+        let ranked = rank_history(&entries, &existing, 2);
+        let summary: Vec<(&str, usize)> = ranked.iter().map(|s| (s.command.as_str(), s.count)).collect();
+        // Ties go to the most recently used
+        assert_eq!(summary, [("git status", 2), ("cargo test --workspace", 2)]);
+        assert_eq!(ranked[0].shells, ["bash", "zsh"]);
+        assert_eq!(ranked[0].title, "Git status");
+        assert_eq!(ranked[0].category, "git");
+        assert_eq!(ranked[0].last_used.as_deref(), Some("2023-11-14T22:21:40+00:00"));
+        assert_eq!(ranked[1].title, "Cargo test");
+
+        assert_eq!(rank_history(&entries, &existing, 0).len(), 3);
+    }
+}
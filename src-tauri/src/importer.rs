@@ -0,0 +1,397 @@
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::lots::{default_currency, insert_transaction, normalize_symbol, PortfolioTransaction};
+use crate::DB;
+
+/// Maps broker CSV headers onto ledger fields. Header matching is case-insensitive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    date: String,
+    symbol: String,
+    #[serde(default)]
+    side: Option<String>,
+    quantity: String,
+    price: String,
+    #[serde(default)]
+    fees: Option<String>,
+    #[serde(default)]
+    currency: Option<String>,
+    #[serde(default)]
+    date_format: Option<String>,
+    #[serde(default)]
+    delimiter: Option<char>,
+    #[serde(default)]
+    buy_values: Vec<String>,
+    #[serde(default)]
+    sell_values: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportMapping {
+    id: Option<i32>,
+    name: String,
+    mapping: ColumnMapping,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedTrade {
+    trade_date: String,
+    symbol: String,
+    side: String,
+    quantity: f64,
+    price: f64,
+    fees: f64,
+    currency: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportRow {
+    line: usize,
+    hash: String,
+    status: String,
+    trade: Option<ParsedTrade>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportPreview {
+    rows: Vec<ImportRow>,
+    new_count: usize,
+    duplicate_count: usize,
+    error_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportResult {
+    imported: usize,
+    skipped_duplicates: usize,
+    errors: Vec<ImportRow>,
+}
+
+const DEFAULT_BUY_VALUES: [&str; 4] = ["buy", "bought", "b", "purchase"];
+const DEFAULT_SELL_VALUES: [&str; 4] = ["sell", "sold", "s", "sale"];
+const DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%m/%d/%Y", "%Y/%m/%d", "%d-%b-%Y", "%Y%m%d"];
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS import_mappings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            mapping TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS imported_rows (
+            hash TEXT PRIMARY KEY,
+            transaction_id INTEGER NOT NULL,
+            source TEXT,
+            imported_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    let trimmed = value.trim();
+    // Accounting exports write negatives as "(12.50)".
+    let (negative, body) = match trimmed.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(inner) => (true, inner),
+        None => (false, trimmed),
+    };
+    let cleaned: String = body.chars().filter(|c| !matches!(c, '$' | ',' | ' ' | '€' | '£')).collect();
+    if cleaned.is_empty() {
+        return Ok(0.0);
+    }
+    let number: f64 = cleaned.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    Ok(if negative { -number } else { number })
+}
+
+fn parse_date(value: &str, format: Option<&str>) -> Result<NaiveDate, String> {
+    let trimmed = value.trim();
+    // Many brokers append a time; the first token is the date.
+    let day = trimmed.split(['T', ' ']).next().unwrap_or(trimmed);
+    let formats: Vec<&str> = match format {
+        Some(format) => vec![format],
+        None => DATE_FORMATS.to_vec(),
+    };
+    for format in formats {
+        if let Ok(date) = NaiveDate::parse_from_str(trimmed, format) {
+            return Ok(date);
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(day, format) {
+            return Ok(date);
+        }
+    }
+    Err(format!("Unrecognized date '{}'", value))
+}
+
+fn row_hash(trade: &ParsedTrade, occurrence: usize) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "{}|{}|{}|{}|{}|{}|{}|{}",
+        trade.trade_date, trade.symbol, trade.side, trade.quantity, trade.price, trade.fees, trade.currency, occurrence
+    ));
+    format!("{:x}", hasher.finalize())
+}
+
+fn resolve_side(mapping: &ColumnMapping, raw: Option<&str>, quantity: f64) -> Result<String, String> {
+    let raw = match raw {
+        Some(raw) if !raw.trim().is_empty() => raw.trim().to_lowercase(),
+        // Without a side column the sign of the quantity decides.
+        _ => return Ok(if quantity < 0.0 { "sell" } else { "buy" }.to_string()),
+    };
+    let matches = |custom: &[String], defaults: &[&str]| {
+        if custom.is_empty() {
+            defaults.contains(&raw.as_str())
+        } else {
+            custom.iter().any(|v| v.trim().eq_ignore_ascii_case(&raw))
+        }
+    };
+    if matches(&mapping.buy_values, &DEFAULT_BUY_VALUES) {
+        Ok("buy".to_string())
+    } else if matches(&mapping.sell_values, &DEFAULT_SELL_VALUES) {
+        Ok("sell".to_string())
+    } else {
+        Err(format!("Unrecognized side '{}'", raw))
+    }
+}
+
+/// Parses the CSV and classifies every row as new, duplicate or error against `imported_rows`.
+pub fn build_preview(conn: &Connection, contents: &str, mapping: &ColumnMapping) -> Result<ImportPreview, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(mapping.delimiter.unwrap_or(',') as u8)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes());
+
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| format!("CSV error: {}", e))?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+    let column = |name: &str| -> Result<usize, String> {
+        headers.iter().position(|h| *h == name.to_lowercase())
+            .ok_or_else(|| format!("Column '{}' not found in CSV header", name))
+    };
+    let optional_column = |name: &Option<String>| -> Result<Option<usize>, String> {
+        name.as_deref().map(column).transpose()
+    };
+
+    let date_col = column(&mapping.date)?;
+    let symbol_col = column(&mapping.symbol)?;
+    let quantity_col = column(&mapping.quantity)?;
+    let price_col = column(&mapping.price)?;
+    let side_col = optional_column(&mapping.side)?;
+    let fees_col = optional_column(&mapping.fees)?;
+    let currency_col = optional_column(&mapping.currency)?;
+
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    let mut rows = Vec::new();
+
+    for (index, record) in reader.records().enumerate() {
+        // Line 1 is the header.
+        let line = index + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                rows.push(ImportRow { line, hash: String::new(), status: "error".to_string(), trade: None, error: Some(format!("CSV error: {}", e)) });
+                continue;
+            }
+        };
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+
+        let field = |col: usize| record.get(col).unwrap_or("");
+        let parsed = (|| -> Result<ParsedTrade, String> {
+            let quantity = parse_number(field(quantity_col))?;
+            let side = resolve_side(mapping, side_col.map(field), quantity)?;
+            let symbol = normalize_symbol(field(symbol_col));
+            if symbol.is_empty() {
+                return Err("Missing symbol".to_string());
+            }
+            Ok(ParsedTrade {
+                trade_date: parse_date(field(date_col), mapping.date_format.as_deref())?.format("%Y-%m-%d").to_string(),
+                symbol,
+                side,
+                quantity: quantity.abs(),
+                price: parse_number(field(price_col))?.abs(),
+                fees: fees_col.map(|col| parse_number(field(col))).transpose()?.unwrap_or(0.0).abs(),
+                currency: currency_col.map(field).filter(|c| !c.is_empty()).map(|c| c.to_uppercase()).unwrap_or_else(default_currency),
+            })
+        })();
+
+        match parsed {
+            Ok(trade) => {
+                // Identical fills in one statement are legitimate, so each copy gets its own hash.
+                let key = row_hash(&trade, 0);
+                let occurrence = occurrences.entry(key).or_insert(0);
+                let hash = row_hash(&trade, *occurrence);
+                *occurrence += 1;
+
+                let exists: bool = conn.query_row(
+                    "SELECT COUNT(*) > 0 FROM imported_rows WHERE hash = ?",
+                    params![hash],
+                    |row| row.get(0),
+                ).map_err(|e| format!("Database error: {}", e))?;
+
+                let status = if exists { "duplicate" } else { "new" };
+                rows.push(ImportRow { line, hash, status: status.to_string(), trade: Some(trade), error: None });
+            }
+            Err(error) => {
+                rows.push(ImportRow { line, hash: String::new(), status: "error".to_string(), trade: None, error: Some(error) });
+            }
+        }
+    }
+
+    let count = |status: &str| rows.iter().filter(|r| r.status == status).count();
+    Ok(ImportPreview {
+        new_count: count("new"),
+        duplicate_count: count("duplicate"),
+        error_count: count("error"),
+        rows,
+    })
+}
+
+fn read_statement(file_path: &str) -> Result<String, String> {
+    std::fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))
+}
+
+// Import commands
+#[tauri::command]
+pub fn preview_portfolio_import(file_path: String, mapping: ColumnMapping) -> Result<ImportPreview, String> {
+    let contents = read_statement(&file_path)?;
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    build_preview(conn, &contents, &mapping)
+}
+
+#[tauri::command]
+pub fn commit_portfolio_import(file_path: String, mapping: ColumnMapping) -> Result<ImportResult, String> {
+    let contents = read_statement(&file_path)?;
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let preview = build_preview(conn, &contents, &mapping)?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let mut new_rows: Vec<&ImportRow> = preview.rows.iter().filter(|r| r.status == "new").collect();
+    // Sells need their lots to exist first, so apply in trade order.
+    new_rows.sort_by(|a, b| {
+        let key = |row: &ImportRow| row.trade.as_ref().map(|t| (t.trade_date.clone(), t.side == "sell"));
+        key(a).cmp(&key(b)).then(a.line.cmp(&b.line))
+    });
+
+    // The whole statement lands or none of it does.
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    for row in &new_rows {
+        let trade = match &row.trade {
+            Some(trade) => trade,
+            None => continue,
+        };
+        let transaction = PortfolioTransaction {
+            id: None,
+            symbol: trade.symbol.clone(),
+            side: trade.side.clone(),
+            quantity: trade.quantity,
+            price: trade.price,
+            fees: trade.fees,
+            currency: trade.currency.clone(),
+            trade_date: trade.trade_date.clone(),
+            cost_basis_method: None,
+            lot_selections: Vec::new(),
+            notes: format!("Imported from {}", file_path),
+        };
+        let transaction_id = insert_transaction(&tx, &transaction)
+            .map_err(|e| format!("Line {}: {}", row.line, e))?;
+
+        tx.execute(
+            "INSERT INTO imported_rows (hash, transaction_id, source, imported_at) VALUES (?, ?, ?, ?)",
+            params![row.hash, transaction_id, file_path, now],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(ImportResult {
+        imported: new_rows.len(),
+        skipped_duplicates: preview.duplicate_count,
+        errors: preview.rows.into_iter().filter(|r| r.status == "error").collect(),
+    })
+}
+
+#[tauri::command]
+pub fn get_import_mappings() -> Result<Vec<ImportMapping>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = conn.prepare("SELECT id, name, mapping, created_at FROM import_mappings ORDER BY name")
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mappings = stmt.query_map([], |row| {
+        let mapping_json: String = row.get(2)?;
+        Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, mapping_json, row.get::<_, String>(3)?))
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for mapping in mappings {
+        let (id, name, mapping_json, created_at) = mapping.map_err(|e| format!("Database error: {}", e))?;
+        let mapping = serde_json::from_str(&mapping_json)
+            .map_err(|e| format!("JSON error: {}", e))?;
+        result.push(ImportMapping { id: Some(id), name, mapping, created_at });
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn save_import_mapping(mapping: ImportMapping) -> Result<i32, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mapping_json = serde_json::to_string(&mapping.mapping)
+        .map_err(|e| format!("JSON error: {}", e))?;
+
+    if let Some(id) = mapping.id {
+        // Update existing mapping
+        conn.execute(
+            "UPDATE import_mappings SET name = ?, mapping = ? WHERE id = ?",
+            params![mapping.name, mapping_json, id],
+        ).map_err(|e| format!("Database error: {}", e))?;
+        Ok(id)
+    } else {
+        // Insert new mapping
+        conn.execute(
+            "INSERT INTO import_mappings (name, mapping, created_at) VALUES (?, ?, ?)",
+            params![mapping.name, mapping_json, now],
+        ).map_err(|e| format!("Database error: {}", e))?;
+        Ok(conn.last_insert_rowid() as i32)
+    }
+}
+
+#[tauri::command]
+pub fn delete_import_mapping(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    conn.execute("DELETE FROM import_mappings WHERE id = ?", params![id])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
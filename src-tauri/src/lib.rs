@@ -6,6 +6,33 @@ use tokio::io::AsyncReadExt;
 use rusqlite::{Connection, Result as SqliteResult, params};
 use lazy_static::lazy_static;
 use rfd::FileDialog;
+use query::{ListOptions, ListSpec, Page};
+
+mod alerts;
+mod attachments;
+mod expansion;
+mod highlight;
+mod history;
+mod importer;
+mod links;
+mod lots;
+mod markdown;
+mod performance;
+mod prices;
+mod query;
+mod revisions;
+mod runner;
+mod search;
+mod snippet_folders;
+mod snippet_io;
+mod snippet_sync;
+mod tags;
+mod templates;
+#[cfg(test)]
+mod test_support;
+mod trash;
+mod usage;
+mod vault;
 
 lazy_static! {
     static ref DB: Mutex<Option<Connection>> = Mutex::new(None);
@@ -35,6 +62,30 @@ struct Settings {
     theme: String,
     auto_upload: bool,
     user_type: String,
+    /// Revisions kept per note; 0 keeps every revision.
+    #[serde(default = "default_note_revision_limit")]
+    note_revision_limit: i64,
+    /// Revisions older than this many days are pruned; 0 never expires them.
+    #[serde(default)]
+    note_revision_max_age_days: i64,
+    /// Days a deleted item stays in the trash before it is purged; 0 keeps it until emptied.
+    #[serde(default = "default_trash_retention_days")]
+    trash_retention_days: i64,
+    /// Idle minutes before the encrypted-notes vault locks again.
+    #[serde(default = "default_vault_lock_minutes")]
+    vault_lock_minutes: i64,
+}
+
+fn default_note_revision_limit() -> i64 {
+    50
+}
+
+fn default_trash_retention_days() -> i64 {
+    30
+}
+
+fn default_vault_lock_minutes() -> i64 {
+    15
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,6 +96,12 @@ struct Note {
     category: String,
     created_at: String,
     updated_at: String,
+    /// Comma-separated; left unchanged on save when absent.
+    #[serde(default)]
+    tags: Option<String>,
+    /// Encrypted notes come back with empty content while the vault is locked.
+    #[serde(default)]
+    encrypted: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -82,6 +139,8 @@ struct PortfolioItem {
     gain_loss: f64,
     gain_loss_percent: f64,
     notes: String,
+    #[serde(default)]
+    closed_at: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -94,6 +153,15 @@ struct CodeSnippet {
     tags: String,
     created_at: String,
     updated_at: String,
+    /// Set when the code uses tab stops or variables; see `expand_snippet`.
+    #[serde(default)]
+    expandable: bool,
+    /// Trigger text used by editor snippet formats.
+    #[serde(default)]
+    prefix: Option<String>,
+    /// Folder the snippet is filed in. Only read when creating; `move_snippets_to_folder` moves it later.
+    #[serde(default)]
+    folder_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -105,6 +173,13 @@ struct CommandTemplate {
     category: String,
     tags: String,
     created_at: String,
+    #[serde(default)]
+    use_count: i64,
+    #[serde(default)]
+    last_used_at: Option<String>,
+    /// Decayed use count; see `usage::current_score`.
+    #[serde(default)]
+    frecency: f64,
 }
 
 // Initialize database
@@ -121,7 +196,11 @@ fn init_db() -> SqliteResult<()> {
             s3_secret_access_key TEXT,
             theme TEXT DEFAULT 'light',
             auto_upload BOOLEAN DEFAULT 0,
-            user_type TEXT DEFAULT 'normal'
+            user_type TEXT DEFAULT 'normal',
+            note_revision_limit INTEGER DEFAULT 50,
+            note_revision_max_age_days INTEGER DEFAULT 0,
+            trash_retention_days INTEGER DEFAULT 30,
+            vault_lock_minutes INTEGER DEFAULT 15
         )",
         [],
     )?;
@@ -169,10 +248,14 @@ fn init_db() -> SqliteResult<()> {
             total_value REAL DEFAULT 0,
             gain_loss REAL DEFAULT 0,
             gain_loss_percent REAL DEFAULT 0,
-            notes TEXT
+            notes TEXT,
+            closed_at TEXT
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "portfolio", "closed_at", "TEXT")?;
+    merge_duplicate_positions(&conn)?;
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_portfolio_symbol ON portfolio(symbol)", [])?;
     
     // Code snippets table
     conn.execute(
@@ -184,10 +267,12 @@ fn init_db() -> SqliteResult<()> {
             language TEXT DEFAULT 'text',
             tags TEXT,
             created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            updated_at TEXT NOT NULL,
+            prefix TEXT
         )",
         [],
     )?;
+    add_column_if_missing(&conn, "code_snippets", "prefix", "TEXT")?;
     
     // Command templates table
     conn.execute(
@@ -203,6 +288,26 @@ fn init_db() -> SqliteResult<()> {
         [],
     )?;
     
+    lots::init_tables(&conn)?;
+    prices::init_tables(&conn)?;
+    importer::init_tables(&conn)?;
+    alerts::init_tables(&conn)?;
+    search::init_tables(&conn)?;
+    revisions::init_tables(&conn)?;
+    links::init_tables(&conn)?;
+    tags::init_tables(&conn)?;
+    attachments::init_tables(&conn)?;
+    vault::init_tables(&conn)?;
+    runner::init_tables(&conn)?;
+    usage::init_tables(&conn)?;
+    snippet_folders::init_tables(&conn)?;
+    snippet_sync::init_tables(&conn)?;
+    add_column_if_missing(&conn, "settings", "note_revision_limit", "INTEGER DEFAULT 50")?;
+    add_column_if_missing(&conn, "settings", "note_revision_max_age_days", "INTEGER DEFAULT 0")?;
+    add_column_if_missing(&conn, "settings", "trash_retention_days", "INTEGER DEFAULT 30")?;
+    add_column_if_missing(&conn, "settings", "vault_lock_minutes", "INTEGER DEFAULT 15")?;
+    trash::init_tables(&conn)?;
+    
     // Insert default settings if none exist
     let count: i32 = conn.query_row("SELECT COUNT(*) FROM settings", [], |row| row.get(0))?;
     if count == 0 {
@@ -213,10 +318,61 @@ fn init_db() -> SqliteResult<()> {
         )?;
     }
     
+    if let Err(e) = trash::purge_expired(&conn) {
+        eprintln!("Failed to purge expired trash: {}", e);
+    }
+    
     *DB.lock().unwrap() = Some(conn);
     Ok(())
 }
 
+// Adds a column to an existing table; CREATE TABLE IF NOT EXISTS won't touch older databases.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> SqliteResult<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?", table),
+        params![column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+// Collapses rows that share a symbol (ignoring case) into one position so the unique index can be built.
+fn merge_duplicate_positions(conn: &Connection) -> SqliteResult<()> {
+    let mut stmt = conn.prepare("SELECT UPPER(TRIM(symbol)) FROM portfolio GROUP BY UPPER(TRIM(symbol)) HAVING COUNT(*) > 1")?;
+    let duplicates: Vec<String> = stmt.query_map([], |row| row.get(0))?.collect::<SqliteResult<_>>()?;
+
+    for symbol in duplicates {
+        let (keep_id, shares, cost, current_price, notes): (i32, f64, f64, f64, Option<String>) = conn.query_row(
+            "SELECT MIN(id), SUM(shares), SUM(shares * avg_price), MAX(current_price), GROUP_CONCAT(NULLIF(notes, ''), '; ')
+             FROM portfolio WHERE UPPER(TRIM(symbol)) = ?",
+            params![symbol],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?;
+        let (avg_price, total_value, gain_loss, gain_loss_percent) = position_totals(shares, cost, current_price);
+
+        conn.execute("DELETE FROM portfolio WHERE UPPER(TRIM(symbol)) = ? AND id != ?", params![symbol, keep_id])?;
+        conn.execute(
+            "UPDATE portfolio SET shares = ?, avg_price = ?, current_price = ?, total_value = ?, gain_loss = ?, gain_loss_percent = ?, notes = ? WHERE id = ?",
+            params![shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent, notes.unwrap_or_default(), keep_id],
+        )?;
+    }
+
+    conn.execute("UPDATE portfolio SET symbol = UPPER(TRIM(symbol))", [])?;
+    Ok(())
+}
+
+// Returns (avg_price, total_value, gain_loss, gain_loss_percent) for a position.
+fn position_totals(shares: f64, cost: f64, current_price: f64) -> (f64, f64, f64, f64) {
+    let avg_price = if shares > 0.0 { cost / shares } else { 0.0 };
+    let total_value = shares * current_price;
+    let gain_loss = total_value - cost;
+    let gain_loss_percent = if cost > 0.0 { gain_loss / cost * 100.0 } else { 0.0 };
+    (avg_price, total_value, gain_loss, gain_loss_percent)
+}
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -276,7 +432,9 @@ fn get_settings() -> Result<Settings, String> {
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
     let row = conn.query_row(
-        "SELECT s3_bucket, s3_region, s3_access_key_id, s3_secret_access_key, theme, auto_upload, user_type FROM settings LIMIT 1",
+        "SELECT s3_bucket, s3_region, s3_access_key_id, s3_secret_access_key, theme, auto_upload, user_type,
+         COALESCE(note_revision_limit, 50), COALESCE(note_revision_max_age_days, 0),
+         COALESCE(trash_retention_days, 30), COALESCE(vault_lock_minutes, 15) FROM settings LIMIT 1",
         [],
         |row| {
             Ok(Settings {
@@ -289,6 +447,10 @@ fn get_settings() -> Result<Settings, String> {
                 theme: row.get(4)?,
                 auto_upload: row.get(5)?,
                 user_type: row.get(6)?,
+                note_revision_limit: row.get(7)?,
+                note_revision_max_age_days: row.get(8)?,
+                trash_retention_days: row.get(9)?,
+                vault_lock_minutes: row.get(10)?,
             })
         },
     ).map_err(|e| format!("Database error: {}", e))?;
@@ -304,7 +466,8 @@ fn save_settings(settings: Settings) -> Result<(), String> {
     conn.execute(
         "UPDATE settings SET 
          s3_bucket = ?, s3_region = ?, s3_access_key_id = ?, s3_secret_access_key = ?, 
-         theme = ?, auto_upload = ?, user_type = ?",
+         theme = ?, auto_upload = ?, user_type = ?,
+         note_revision_limit = ?, note_revision_max_age_days = ?, trash_retention_days = ?, vault_lock_minutes = ?",
         params![
             settings.s3_config.bucket,
             settings.s3_config.region,
@@ -313,6 +476,10 @@ fn save_settings(settings: Settings) -> Result<(), String> {
             settings.theme,
             if settings.auto_upload { 1 } else { 0 },
             settings.user_type,
+            settings.note_revision_limit.max(0),
+            settings.note_revision_max_age_days.max(0),
+            settings.trash_retention_days.max(0),
+            settings.vault_lock_minutes.max(1),
         ],
     ).map_err(|e| format!("Database error: {}", e))?;
     
@@ -320,31 +487,60 @@ fn save_settings(settings: Settings) -> Result<(), String> {
 }
 
 // Notes commands
+const NOTE_LIST: ListSpec = ListSpec {
+    table: "notes",
+    filter: Some("deleted_at IS NULL"),
+    columns: concat!(
+        "id, title, content, category, created_at, updated_at, ",
+        tags::tag_names_sql!("note", "notes.id"),
+        ", COALESCE(encrypted, 0), ciphertext, nonce"
+    ),
+    sortable: &[
+        ("updated_at", "updated_at"),
+        ("created_at", "created_at"),
+        ("title", "title COLLATE NOCASE"),
+        ("category", "COALESCE(category, '')"),
+    ],
+    default_sort: "updated_at",
+    default_desc: true,
+    category_column: Some("category"),
+    status_column: None,
+    date_column: Some("updated_at"),
+    tag_kind: Some("note"),
+    folders: None,
+};
+
 #[tauri::command]
-fn get_notes() -> Result<Vec<Note>, String> {
+fn get_notes(options: Option<ListOptions>) -> Result<Page<Note>, String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    let mut stmt = conn.prepare("SELECT id, title, content, category, created_at, updated_at FROM notes ORDER BY updated_at DESC")
-        .map_err(|e| format!("Database error: {}", e))?;
-    
-    let notes = stmt.query_map([], |row| {
+    // Locked notes are listed by metadata only
+    let key = vault::session_key();
+    query::fetch_page(conn, &NOTE_LIST, &options.unwrap_or_default(), |row| {
+        let id: i32 = row.get(0)?;
+        let encrypted: bool = row.get(7)?;
+        let content = if encrypted {
+            let ciphertext: Option<Vec<u8>> = row.get(8)?;
+            let nonce: Option<Vec<u8>> = row.get(9)?;
+            key.as_ref()
+                .zip(ciphertext.zip(nonce))
+                .and_then(|(key, (ciphertext, nonce))| vault::decrypt_with(key, id, &ciphertext, &nonce))
+                .unwrap_or_default()
+        } else {
+            row.get(2)?
+        };
         Ok(Note {
-            id: row.get(0)?,
+            id: Some(id),
             title: row.get(1)?,
-            content: row.get(2)?,
+            content,
             category: row.get(3)?,
             created_at: row.get(4)?,
             updated_at: row.get(5)?,
+            tags: row.get(6)?,
+            encrypted,
         })
-    }).map_err(|e| format!("Database error: {}", e))?;
-    
-    let mut result = Vec::new();
-    for note in notes {
-        result.push(note.map_err(|e| format!("Database error: {}", e))?);
-    }
-    
-    Ok(result)
+    })
 }
 
 #[tauri::command]
@@ -354,21 +550,46 @@ fn save_note(note: Note) -> Result<i32, String> {
     
     let now = chrono::Utc::now().to_rfc3339();
     
-    if let Some(id) = note.id {
-        // Update existing note
+    // An existing note keeps its stored encryption; use encrypt_note/decrypt_note to change it
+    let encrypted = match note.id {
+        Some(id) => vault::is_encrypted(conn, id),
+        None => note.encrypted,
+    };
+    if encrypted && vault::session_key().is_none() {
+        return Err("Unlock the vault to save encrypted notes".to_string());
+    }
+    let plaintext = if encrypted { "" } else { note.content.as_str() };
+    
+    let id = if let Some(id) = note.id {
+        // Update existing note, keeping its pre-edit state in the history
+        if !encrypted {
+            revisions::snapshot_note(conn, id)?;
+        }
         conn.execute(
             "UPDATE notes SET title = ?, content = ?, category = ?, updated_at = ? WHERE id = ?",
-            params![note.title, note.content, note.category, now, id],
+            params![note.title, plaintext, note.category, now, id],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(id)
+        id
     } else {
         // Insert new note
-        let id = conn.execute(
+        conn.execute(
             "INSERT INTO notes (title, content, category, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
-            params![note.title, note.content, note.category, now, now],
+            params![note.title, plaintext, note.category, now, now],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(conn.last_insert_rowid() as i32)
+        conn.last_insert_rowid() as i32
+    };
+
+    if encrypted {
+        vault::write_encrypted_content(conn, id, &note.content)?;
+        links::resolve_dangling(conn)?;
+    } else {
+        revisions::snapshot_note(conn, id)?;
+        links::sync_note_links(conn, id, &note.content)?;
+    }
+    if let Some(note_tags) = &note.tags {
+        tags::set_entity_tags(conn, "note", id, &tags::parse_tags(note_tags))?;
     }
+    Ok(id)
 }
 
 #[tauri::command]
@@ -376,22 +597,37 @@ fn delete_note(id: i32) -> Result<(), String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    conn.execute("DELETE FROM notes WHERE id = ?", params![id])
-        .map_err(|e| format!("Database error: {}", e))?;
-    
-    Ok(())
+    trash::move_to_trash(conn, "note", id)
 }
 
 // Invoice commands
+const INVOICE_LIST: ListSpec = ListSpec {
+    table: "invoices",
+    filter: Some("deleted_at IS NULL"),
+    columns: "id, invoice_number, client_name, client_email, items, subtotal, tax_rate, tax_amount, total, status, due_date, created_at",
+    sortable: &[
+        ("created_at", "created_at"),
+        ("due_date", "COALESCE(due_date, '')"),
+        ("invoice_number", "invoice_number"),
+        ("client_name", "client_name COLLATE NOCASE"),
+        ("total", "COALESCE(total, 0)"),
+        ("status", "status"),
+    ],
+    default_sort: "created_at",
+    default_desc: true,
+    category_column: None,
+    status_column: Some("status"),
+    date_column: Some("created_at"),
+    tag_kind: None,
+    folders: None,
+};
+
 #[tauri::command]
-fn get_invoices() -> Result<Vec<Invoice>, String> {
+fn get_invoices(options: Option<ListOptions>) -> Result<Page<Invoice>, String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    let mut stmt = conn.prepare("SELECT id, invoice_number, client_name, client_email, items, subtotal, tax_rate, tax_amount, total, status, due_date, created_at FROM invoices ORDER BY created_at DESC")
-        .map_err(|e| format!("Database error: {}", e))?;
-    
-    let invoices = stmt.query_map([], |row| {
+    query::fetch_page(conn, &INVOICE_LIST, &options.unwrap_or_default(), |row| {
         let items_json: String = row.get(4)?;
         let items: Vec<InvoiceItem> = serde_json::from_str(&items_json).unwrap_or_default();
         
@@ -409,14 +645,7 @@ fn get_invoices() -> Result<Vec<Invoice>, String> {
             due_date: row.get(10)?,
             created_at: row.get(11)?,
         })
-    }).map_err(|e| format!("Database error: {}", e))?;
-    
-    let mut result = Vec::new();
-    for invoice in invoices {
-        result.push(invoice.map_err(|e| format!("Database error: {}", e))?);
-    }
-    
-    Ok(result)
+    })
 }
 
 #[tauri::command]
@@ -434,6 +663,7 @@ fn save_invoice(invoice: Invoice) -> Result<i32, String> {
             "UPDATE invoices SET invoice_number = ?, client_name = ?, client_email = ?, items = ?, subtotal = ?, tax_rate = ?, tax_amount = ?, total = ?, status = ?, due_date = ? WHERE id = ?",
             params![invoice.invoice_number, invoice.client_name, invoice.client_email, items_json, invoice.subtotal, invoice.tax_rate, invoice.tax_amount, invoice.total, invoice.status, invoice.due_date, id],
         ).map_err(|e| format!("Database error: {}", e))?;
+        links::resolve_dangling(conn)?;
         Ok(id)
     } else {
         // Insert new invoice
@@ -441,20 +671,56 @@ fn save_invoice(invoice: Invoice) -> Result<i32, String> {
             "INSERT INTO invoices (invoice_number, client_name, client_email, items, subtotal, tax_rate, tax_amount, total, status, due_date, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![invoice.invoice_number, invoice.client_name, invoice.client_email, items_json, invoice.subtotal, invoice.tax_rate, invoice.tax_amount, invoice.total, invoice.status, invoice.due_date, now],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(conn.last_insert_rowid() as i32)
+        let id = conn.last_insert_rowid() as i32;
+        links::resolve_dangling(conn)?;
+        Ok(id)
     }
 }
 
+#[tauri::command]
+fn delete_invoice(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    trash::move_to_trash(conn, "invoice", id)
+}
+
 // Portfolio commands
+// Status is derived: positions are "open" until closed.
+const PORTFOLIO_LIST: ListSpec = ListSpec {
+    table: "portfolio",
+    filter: Some("deleted_at IS NULL"),
+    columns: "id, symbol, shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent, notes, closed_at",
+    sortable: &[
+        ("symbol", "symbol"),
+        ("shares", "shares"),
+        ("total_value", "COALESCE(total_value, 0)"),
+        ("gain_loss", "COALESCE(gain_loss, 0)"),
+        ("gain_loss_percent", "COALESCE(gain_loss_percent, 0)"),
+    ],
+    default_sort: "symbol",
+    default_desc: false,
+    category_column: None,
+    status_column: Some("CASE WHEN closed_at IS NULL THEN 'open' ELSE 'closed' END"),
+    date_column: None,
+    tag_kind: None,
+    folders: None,
+};
+
 #[tauri::command]
-fn get_portfolio() -> Result<Vec<PortfolioItem>, String> {
+fn get_portfolio(options: Option<ListOptions>) -> Result<Page<PortfolioItem>, String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    let mut stmt = conn.prepare("SELECT id, symbol, shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent, notes FROM portfolio ORDER BY symbol")
-        .map_err(|e| format!("Database error: {}", e))?;
+    // Closed positions stay hidden unless asked for; "all" lifts the filter
+    let mut options = options.unwrap_or_default();
+    options.status = match options.status.as_deref() {
+        None => Some("open".to_string()),
+        Some("all") => None,
+        Some(_) => options.status,
+    };
     
-    let items = stmt.query_map([], |row| {
+    query::fetch_page(conn, &PORTFOLIO_LIST, &options, |row| {
         Ok(PortfolioItem {
             id: row.get(0)?,
             symbol: row.get(1)?,
@@ -465,67 +731,195 @@ fn get_portfolio() -> Result<Vec<PortfolioItem>, String> {
             gain_loss: row.get(6)?,
             gain_loss_percent: row.get(7)?,
             notes: row.get(8)?,
+            closed_at: row.get(9)?,
         })
-    }).map_err(|e| format!("Database error: {}", e))?;
-    
-    let mut result = Vec::new();
-    for item in items {
-        result.push(item.map_err(|e| format!("Database error: {}", e))?);
-    }
-    
-    Ok(result)
+    })
 }
 
 #[tauri::command]
-fn save_portfolio_item(item: PortfolioItem) -> Result<i32, String> {
+fn save_portfolio_item(app: tauri::AppHandle, item: PortfolioItem) -> Result<i32, String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    if let Some(id) = item.id {
+    let symbol = lots::normalize_symbol(&item.symbol);
+    if symbol.is_empty() {
+        return Err("Symbol is required".to_string());
+    }
+    
+    // A trashed position still owns the symbol's ledger, so it has to be restored or emptied first
+    let trashed: Option<i32> = conn.query_row(
+        "SELECT id FROM portfolio WHERE symbol = ? AND deleted_at IS NOT NULL",
+        params![symbol],
+        |row| row.get(0),
+    ).ok();
+    if trashed.is_some_and(|trashed_id| Some(trashed_id) != item.id) {
+        return Err(format!("A position for {} is in the trash; restore it or empty the trash first", symbol));
+    }
+    
+    let existing: Option<i32> = conn.query_row(
+        "SELECT id FROM portfolio WHERE symbol = ?",
+        params![symbol],
+        |row| row.get(0),
+    ).ok();
+    
+    let previous_price: f64 = match item.id.or(existing) {
+        Some(id) => conn.query_row("SELECT current_price FROM portfolio WHERE id = ?", params![id], |row| row.get(0)).unwrap_or(0.0),
+        None => 0.0,
+    };
+    
+    let id = if let Some(id) = item.id {
         // Update existing item
-        conn.execute(
-            "UPDATE portfolio SET symbol = ?, shares = ?, avg_price = ?, current_price = ?, total_value = ?, gain_loss = ?, gain_loss_percent = ?, notes = ? WHERE id = ?",
-            params![item.symbol, item.shares, item.avg_price, item.current_price, item.total_value, item.gain_loss, item.gain_loss_percent, item.notes, id],
+        if let Some(other_id) = existing {
+            if other_id != id {
+                return Err(format!("A position for {} already exists", symbol));
+            }
+        }
+        let (stored_symbol, shares, avg_price): (String, f64, f64) = conn.query_row(
+            "SELECT symbol, shares, avg_price FROM portfolio WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).map_err(|_| format!("Portfolio item {} not found", id))?;
+        // Shares and cost come from the position's lots, so they only change through its transactions
+        if (item.shares - shares).abs() > 1e-6 || (item.avg_price - avg_price).abs() > 1e-6 {
+            return Err("Shares and average price follow the position's transactions; record a buy or sell to change them".to_string());
+        }
+
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| format!("Database error: {}", e))?;
+        if stored_symbol != symbol {
+            lots::rename_symbol(&tx, &stored_symbol, &symbol)?;
+        }
+        tx.execute(
+            "UPDATE portfolio SET symbol = ?, current_price = ?, notes = ? WHERE id = ?",
+            params![symbol, item.current_price, item.notes, id],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(id)
+        lots::sync_position(&tx, &symbol, item.current_price)?;
+        tx.commit().map_err(|e| format!("Database error: {}", e))?;
+        id
+    } else if let Some(id) = existing {
+        // Adding to a symbol we already hold is a buy into that position, so it gets its own lot
+        lots::apply_transaction(conn, &lots::PortfolioTransaction {
+            id: None,
+            symbol: symbol.clone(),
+            side: "buy".to_string(),
+            quantity: item.shares,
+            price: item.avg_price,
+            fees: 0.0,
+            currency: lots::default_currency(),
+            trade_date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            cost_basis_method: None,
+            lot_selections: Vec::new(),
+            notes: item.notes,
+        })?;
+        if item.current_price > 0.0 {
+            conn.execute("UPDATE portfolio SET current_price = ? WHERE id = ?", params![item.current_price, id])
+                .map_err(|e| format!("Database error: {}", e))?;
+            lots::sync_position(conn, &symbol, item.current_price)?;
+        }
+        id
     } else {
         // Insert new item
         conn.execute(
             "INSERT INTO portfolio (symbol, shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent, notes) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-            params![item.symbol, item.shares, item.avg_price, item.current_price, item.total_value, item.gain_loss, item.gain_loss_percent, item.notes],
+            params![symbol, item.shares, item.avg_price, item.current_price, item.total_value, item.gain_loss, item.gain_loss_percent, item.notes],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(conn.last_insert_rowid() as i32)
+        let id = conn.last_insert_rowid() as i32;
+        lots::open_position_lot(conn, &symbol, item.shares, item.avg_price)?;
+        id
+    };
+    
+    // A price typed in by hand is checked against alerts like a fetched one
+    let triggered = if item.current_price > 0.0 && (item.current_price - previous_price).abs() > 1e-9 {
+        alerts::evaluate_alerts(conn, &symbol, item.current_price)?
+    } else {
+        Vec::new()
+    };
+    drop(db_guard);
+    
+    alerts::notify_triggered(&app, &triggered);
+    Ok(id)
+}
+
+#[tauri::command]
+fn delete_portfolio_item(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    // The symbol's ledger is only removed when the trash is emptied; use close to keep history
+    trash::move_to_trash(conn, "portfolio", id)
+}
+
+#[tauri::command]
+fn close_portfolio_item(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    let now = chrono::Utc::now().to_rfc3339();
+    let updated = conn.execute("UPDATE portfolio SET closed_at = ? WHERE id = ? AND closed_at IS NULL", params![now, id])
+        .map_err(|e| format!("Database error: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Open portfolio item {} not found", id));
     }
+    
+    Ok(())
 }
 
-// Code snippets commands
 #[tauri::command]
-fn get_code_snippets() -> Result<Vec<CodeSnippet>, String> {
+fn reopen_portfolio_item(id: i32) -> Result<(), String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    let mut stmt = conn.prepare("SELECT id, title, description, code, language, tags, created_at, updated_at FROM code_snippets ORDER BY updated_at DESC")
+    let updated = conn.execute("UPDATE portfolio SET closed_at = NULL WHERE id = ?", params![id])
         .map_err(|e| format!("Database error: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Portfolio item {} not found", id));
+    }
     
-    let snippets = stmt.query_map([], |row| {
+    Ok(())
+}
+
+// Code snippets commands
+// Snippets have no category, so the category filter matches on language.
+const SNIPPET_LIST: ListSpec = ListSpec {
+    table: "code_snippets",
+    filter: Some("deleted_at IS NULL"),
+    columns: concat!("id, title, description, code, language, ", tags::tag_names_sql!("snippet", "code_snippets.id"), ", created_at, updated_at, prefix, folder_id"),
+    sortable: &[
+        ("updated_at", "updated_at"),
+        ("created_at", "created_at"),
+        ("title", "title COLLATE NOCASE"),
+        ("language", "COALESCE(language, '')"),
+    ],
+    default_sort: "updated_at",
+    default_desc: true,
+    category_column: Some("language"),
+    status_column: None,
+    date_column: Some("updated_at"),
+    tag_kind: Some("snippet"),
+    folders: Some(("folder_id", "snippet_folders")),
+};
+
+#[tauri::command]
+fn get_code_snippets(options: Option<ListOptions>) -> Result<Page<CodeSnippet>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    query::fetch_page(conn, &SNIPPET_LIST, &options.unwrap_or_default(), |row| {
+        let code: String = row.get(3)?;
         Ok(CodeSnippet {
             id: row.get(0)?,
             title: row.get(1)?,
             description: row.get(2)?,
-            code: row.get(3)?,
+            expandable: expansion::is_expandable(&code),
+            code,
             language: row.get(4)?,
             tags: row.get(5)?,
             created_at: row.get(6)?,
             updated_at: row.get(7)?,
+            prefix: row.get(8)?,
+            folder_id: row.get(9)?,
         })
-    }).map_err(|e| format!("Database error: {}", e))?;
-    
-    let mut result = Vec::new();
-    for snippet in snippets {
-        result.push(snippet.map_err(|e| format!("Database error: {}", e))?);
-    }
-    
-    Ok(result)
+    })
 }
 
 #[tauri::command]
@@ -533,35 +927,98 @@ fn save_code_snippet(snippet: CodeSnippet) -> Result<i32, String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
+    let id = write_code_snippet(conn, &snippet)?;
+    snippet_sync::record_change(conn);
+    Ok(id)
+}
+
+/// Inserts or updates a snippet and its tags; shared by saving and importing.
+fn write_code_snippet(conn: &Connection, snippet: &CodeSnippet) -> Result<i32, String> {
     let now = chrono::Utc::now().to_rfc3339();
+    // Snippets saved without a real language get a detected one
+    let language = if highlight::needs_detection(&snippet.language) {
+        highlight::detect_language(&snippet.title, &snippet.code)
+    } else {
+        snippet.language.trim().to_lowercase()
+    };
     
-    if let Some(id) = snippet.id {
+    let id = if let Some(id) = snippet.id {
         // Update existing snippet
         conn.execute(
-            "UPDATE code_snippets SET title = ?, description = ?, code = ?, language = ?, tags = ?, updated_at = ? WHERE id = ?",
-            params![snippet.title, snippet.description, snippet.code, snippet.language, snippet.tags, now, id],
+            "UPDATE code_snippets SET title = ?, description = ?, code = ?, language = ?, prefix = ?, updated_at = ? WHERE id = ?",
+            params![snippet.title, snippet.description, snippet.code, language, snippet.prefix, now, id],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(id)
+        id
     } else {
         // Insert new snippet
+        if let Some(folder_id) = snippet.folder_id {
+            snippet_folders::require_folder(conn, folder_id)?;
+        }
         conn.execute(
-            "INSERT INTO code_snippets (title, description, code, language, tags, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            params![snippet.title, snippet.description, snippet.code, snippet.language, snippet.tags, now, now],
+            "INSERT INTO code_snippets (title, description, code, language, prefix, folder_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![snippet.title, snippet.description, snippet.code, language, snippet.prefix, snippet.folder_id, now, now],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(conn.last_insert_rowid() as i32)
-    }
+        conn.last_insert_rowid() as i32
+    };
+    
+    tags::set_entity_tags(conn, "snippet", id, &tags::parse_tags(&snippet.tags))?;
+    links::resolve_dangling(conn)?;
+    Ok(id)
 }
 
-// Command templates commands
 #[tauri::command]
-fn get_command_templates() -> Result<Vec<CommandTemplate>, String> {
+fn delete_code_snippet(id: i32) -> Result<(), String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    let mut stmt = conn.prepare("SELECT id, title, description, command, category, tags, created_at FROM command_templates ORDER BY created_at DESC")
-        .map_err(|e| format!("Database error: {}", e))?;
+    trash::move_to_trash(conn, "snippet", id)?;
+    snippet_sync::record_change(conn);
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_code_snippets(ids: Vec<i32>) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    let deleted = trash::move_many_to_trash(conn, "snippet", &ids)?;
+    snippet_sync::record_change(conn);
+    Ok(deleted)
+}
+
+// Command templates commands
+const COMMAND_TEMPLATE_LIST: ListSpec = ListSpec {
+    table: "command_templates",
+    filter: Some("deleted_at IS NULL"),
+    columns: concat!(
+        "id, title, description, command, category, ",
+        tags::tag_names_sql!("command", "command_templates.id"),
+        ", created_at, COALESCE(use_count, 0), last_used_at, frecency"
+    ),
+    sortable: &[
+        ("created_at", "created_at"),
+        ("title", "title COLLATE NOCASE"),
+        ("category", "COALESCE(category, '')"),
+        ("use_count", "COALESCE(use_count, 0)"),
+        ("last_used_at", "COALESCE(last_used_at, '')"),
+        // Never-used templates sort after every used one
+        ("frecency", "COALESCE(frecency, -1e300)"),
+    ],
+    default_sort: "created_at",
+    default_desc: true,
+    category_column: Some("category"),
+    status_column: None,
+    date_column: Some("created_at"),
+    tag_kind: Some("command"),
+    folders: None,
+};
+
+#[tauri::command]
+fn get_command_templates(options: Option<ListOptions>) -> Result<Page<CommandTemplate>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
-    let templates = stmt.query_map([], |row| {
+    query::fetch_page(conn, &COMMAND_TEMPLATE_LIST, &options.unwrap_or_default(), |row| {
         Ok(CommandTemplate {
             id: row.get(0)?,
             title: row.get(1)?,
@@ -570,15 +1027,11 @@ fn get_command_templates() -> Result<Vec<CommandTemplate>, String> {
             category: row.get(4)?,
             tags: row.get(5)?,
             created_at: row.get(6)?,
+            use_count: row.get(7)?,
+            last_used_at: row.get(8)?,
+            frecency: usage::current_score(row.get(9)?),
         })
-    }).map_err(|e| format!("Database error: {}", e))?;
-    
-    let mut result = Vec::new();
-    for template in templates {
-        result.push(template.map_err(|e| format!("Database error: {}", e))?);
-    }
-    
-    Ok(result)
+    })
 }
 
 #[tauri::command]
@@ -586,23 +1039,44 @@ fn save_command_template(template: CommandTemplate) -> Result<i32, String> {
     let db_guard = DB.lock().unwrap();
     let conn = db_guard.as_ref().ok_or("Database not initialized")?;
     
+    // Reject malformed placeholders up front rather than at render time
+    templates::parse_parameters(&template.command)?;
     let now = chrono::Utc::now().to_rfc3339();
     
-    if let Some(id) = template.id {
+    let id = if let Some(id) = template.id {
         // Update existing template
         conn.execute(
-            "UPDATE command_templates SET title = ?, description = ?, command = ?, category = ?, tags = ? WHERE id = ?",
-            params![template.title, template.description, template.command, template.category, template.tags, id],
+            "UPDATE command_templates SET title = ?, description = ?, command = ?, category = ? WHERE id = ?",
+            params![template.title, template.description, template.command, template.category, id],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(id)
+        id
     } else {
         // Insert new template
         conn.execute(
-            "INSERT INTO command_templates (title, description, command, category, tags, created_at) VALUES (?, ?, ?, ?, ?, ?)",
-            params![template.title, template.description, template.command, template.category, template.tags, now],
+            "INSERT INTO command_templates (title, description, command, category, created_at) VALUES (?, ?, ?, ?, ?)",
+            params![template.title, template.description, template.command, template.category, now],
         ).map_err(|e| format!("Database error: {}", e))?;
-        Ok(conn.last_insert_rowid() as i32)
-    }
+        conn.last_insert_rowid() as i32
+    };
+    
+    tags::set_entity_tags(conn, "command", id, &tags::parse_tags(&template.tags))?;
+    Ok(id)
+}
+
+#[tauri::command]
+fn delete_command_template(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    trash::move_to_trash(conn, "command", id)
+}
+
+#[tauri::command]
+fn delete_command_templates(ids: Vec<i32>) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    
+    trash::move_many_to_trash(conn, "command", &ids)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -615,6 +1089,14 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            if let Err(e) = attachments::collect_garbage_on_startup(app.handle()) {
+                eprintln!("Failed to clean up attachment blobs: {}", e);
+            }
+            vault::spawn_idle_lock(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet, 
             open_file_dialog, 
@@ -626,12 +1108,89 @@ pub fn run() {
             delete_note,
             get_invoices,
             save_invoice,
+            delete_invoice,
             get_portfolio,
             save_portfolio_item,
+            delete_portfolio_item,
+            close_portfolio_item,
+            reopen_portfolio_item,
             get_code_snippets,
             save_code_snippet,
+            delete_code_snippet,
+            delete_code_snippets,
             get_command_templates,
-            save_command_template
+            save_command_template,
+            delete_command_template,
+            delete_command_templates,
+            templates::get_template_parameters,
+            templates::render_command_template,
+            runner::run_command_template,
+            runner::cancel_command_run,
+            runner::list_command_runs,
+            history::preview_history_import,
+            history::commit_history_import,
+            usage::record_command_usage,
+            highlight::render_snippet_html,
+            expansion::expand_snippet,
+            snippet_folders::get_snippet_folders,
+            snippet_folders::save_snippet_folder,
+            snippet_folders::delete_snippet_folder,
+            snippet_folders::move_snippets_to_folder,
+            search::search_snippets,
+            snippet_io::preview_snippet_import,
+            snippet_io::commit_snippet_import,
+            snippet_io::export_snippets,
+            snippet_sync::get_snippet_sync_status,
+            snippet_sync::configure_snippet_sync,
+            snippet_sync::disable_snippet_sync,
+            snippet_sync::commit_snippet_sync,
+            snippet_sync::pull_snippet_sync,
+            snippet_sync::resolve_snippet_sync_conflict,
+            lots::record_portfolio_transaction,
+            lots::get_portfolio_transactions,
+            lots::get_tax_lots,
+            lots::set_tax_lot_acquired_date,
+            lots::get_realized_gains,
+            prices::update_prices,
+            prices::get_price_history,
+            performance::portfolio_performance,
+            importer::preview_portfolio_import,
+            importer::commit_portfolio_import,
+            importer::get_import_mappings,
+            importer::save_import_mapping,
+            importer::delete_import_mapping,
+            alerts::get_price_alerts,
+            alerts::save_price_alert,
+            alerts::delete_price_alert,
+            search::search_notes,
+            search::global_search,
+            revisions::list_note_revisions,
+            revisions::get_note_revision,
+            revisions::diff_note_revisions,
+            revisions::restore_note_revision,
+            trash::list_trash,
+            trash::restore_from_trash,
+            trash::empty_trash,
+            markdown::render_note,
+            links::get_backlinks,
+            links::get_entity_backlinks,
+            links::get_note_links,
+            tags::list_tags,
+            tags::rename_tag,
+            tags::merge_tags,
+            attachments::attach_file_to_note,
+            attachments::attach_data_to_note,
+            attachments::list_note_attachments,
+            attachments::open_note_attachment,
+            attachments::detach_note_attachment,
+            attachments::gc_blobs,
+            vault::vault_status,
+            vault::setup_vault,
+            vault::unlock_vault,
+            vault::lock_vault,
+            vault::change_vault_passphrase,
+            vault::encrypt_note,
+            vault::decrypt_note
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
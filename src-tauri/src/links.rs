@@ -0,0 +1,224 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::DB;
+
+/// Link targets: `(prefix, table, column matched against the link text)`. Unprefixed links point at notes.
+const LINK_TARGETS: [(&str, &str, &str); 3] = [
+    ("note", "notes", "title"),
+    ("snippet", "code_snippets", "title"),
+    ("invoice", "invoices", "invoice_number"),
+];
+
+#[derive(Debug)]
+pub struct WikiLink {
+    pub kind: String,
+    pub target: String,
+    pub context: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteLink {
+    kind: String,
+    target_title: String,
+    /// `None` while nothing with that title exists yet.
+    target_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backlink {
+    note_id: i32,
+    title: String,
+    context: String,
+    updated_at: String,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS note_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_note_id INTEGER NOT NULL,
+            target_kind TEXT NOT NULL,
+            target_title TEXT NOT NULL,
+            target_id INTEGER,
+            context TEXT
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_note_links_source ON note_links(source_note_id)", [])?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_note_links_target ON note_links(target_kind, target_id)", [])?;
+
+    Ok(())
+}
+
+/// Extracts `[[Title]]`, `[[Title|label]]` and `[[snippet:Title]]` style links, once per target.
+pub fn parse_wiki_links(content: &str) -> Vec<WikiLink> {
+    let mut links: Vec<WikiLink> = Vec::new();
+
+    for line in content.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let end = match after.find("]]") {
+                Some(end) => end,
+                None => break,
+            };
+            let inner = after[..end].split('|').next().unwrap_or_default().trim();
+            rest = &after[end + 2..];
+
+            let (kind, target) = match inner.split_once(':') {
+                Some((prefix, target)) if LINK_TARGETS.iter().any(|(kind, ..)| kind.eq_ignore_ascii_case(prefix.trim())) => {
+                    (prefix.trim().to_lowercase(), target.trim())
+                }
+                _ => ("note".to_string(), inner),
+            };
+            if target.is_empty() || target.contains('[') {
+                continue;
+            }
+            if links.iter().any(|link| link.kind == kind && link.target.eq_ignore_ascii_case(target)) {
+                continue;
+            }
+
+            let context: String = line.trim().chars().take(200).collect();
+            links.push(WikiLink { kind, target: target.to_string(), context });
+        }
+    }
+
+    links
+}
+
+/// Replaces the note's outgoing links with the ones in `content`. A link whose text is unchanged
+/// keeps the id it already resolved to, so renaming the target doesn't break it.
+pub fn sync_note_links(conn: &Connection, note_id: i32, content: &str) -> Result<(), String> {
+    let mut stmt = conn.prepare("SELECT target_kind, target_title, target_id FROM note_links WHERE source_note_id = ?")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let rows = stmt.query_map(params![note_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<i32>>(2)?))
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut previous = Vec::new();
+    for row in rows {
+        previous.push(row.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    conn.execute("DELETE FROM note_links WHERE source_note_id = ?", params![note_id])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    for link in parse_wiki_links(content) {
+        let known = previous.iter()
+            .find(|(kind, title, id)| *kind == link.kind && title.eq_ignore_ascii_case(&link.target) && id.is_some())
+            .and_then(|(_, _, id)| *id);
+        let target_id = match known {
+            Some(id) => Some(id),
+            None => resolve_target(conn, &link.kind, &link.target)?,
+        };
+
+        conn.execute(
+            "INSERT INTO note_links (source_note_id, target_kind, target_title, target_id, context) VALUES (?, ?, ?, ?, ?)",
+            params![note_id, link.kind, link.target, target_id, link.context],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    // The note's own title may be what other notes were waiting on
+    resolve_dangling(conn)
+}
+
+fn resolve_target(conn: &Connection, kind: &str, target: &str) -> Result<Option<i32>, String> {
+    let (_, table, column) = LINK_TARGETS.iter()
+        .find(|(name, ..)| *name == kind)
+        .ok_or_else(|| format!("Unknown link kind '{}'", kind))?;
+
+    Ok(conn.query_row(
+        &format!("SELECT id FROM {} WHERE {} = ? COLLATE NOCASE AND deleted_at IS NULL ORDER BY id LIMIT 1", table, column),
+        params![target],
+        |row| row.get(0),
+    ).ok())
+}
+
+/// Points links that had no target yet at anything created since with a matching title. Called
+/// whenever something that can be linked to is saved, renamed or restored.
+pub fn resolve_dangling(conn: &Connection) -> Result<(), String> {
+    for (kind, table, column) in LINK_TARGETS {
+        conn.execute(
+            &format!(
+                "UPDATE note_links SET target_id = (
+                    SELECT id FROM {table} WHERE {column} = note_links.target_title COLLATE NOCASE AND deleted_at IS NULL ORDER BY id LIMIT 1
+                 ) WHERE target_kind = ? AND target_id IS NULL",
+            ),
+            params![kind],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Notes linking to the given entity, most recently updated first.
+pub fn backlinks(conn: &Connection, kind: &str, id: i32) -> Result<Vec<Backlink>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT notes.id, notes.title, COALESCE(note_links.context, ''), notes.updated_at
+         FROM note_links JOIN notes ON notes.id = note_links.source_note_id
+         WHERE note_links.target_kind = ? AND note_links.target_id = ? AND notes.deleted_at IS NULL
+         ORDER BY notes.updated_at DESC",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let links = stmt.query_map(params![kind, id], |row| {
+        Ok(Backlink {
+            note_id: row.get(0)?,
+            title: row.get(1)?,
+            context: row.get(2)?,
+            updated_at: row.get(3)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for link in links {
+        result.push(link.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+// Note link commands
+#[tauri::command]
+pub fn get_backlinks(note_id: i32) -> Result<Vec<Backlink>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    backlinks(conn, "note", note_id)
+}
+
+#[tauri::command]
+pub fn get_entity_backlinks(kind: String, id: i32) -> Result<Vec<Backlink>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if !LINK_TARGETS.iter().any(|(name, ..)| *name == kind) {
+        return Err(format!("Unknown link kind '{}'", kind));
+    }
+    backlinks(conn, &kind, id)
+}
+
+#[tauri::command]
+pub fn get_note_links(note_id: i32) -> Result<Vec<NoteLink>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT target_kind, target_title, target_id FROM note_links WHERE source_note_id = ? ORDER BY id",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let links = stmt.query_map(params![note_id], |row| {
+        Ok(NoteLink {
+            kind: row.get(0)?,
+            target_title: row.get(1)?,
+            target_id: row.get(2)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for link in links {
+        result.push(link.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
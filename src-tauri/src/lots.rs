@@ -0,0 +1,796 @@
+use chrono::{Months, NaiveDate};
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::{add_column_if_missing, DB};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostBasisMethod {
+    Fifo,
+    Lifo,
+    Average,
+    SpecificLot,
+}
+
+impl CostBasisMethod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CostBasisMethod::Fifo => "fifo",
+            CostBasisMethod::Lifo => "lifo",
+            CostBasisMethod::Average => "average",
+            CostBasisMethod::SpecificLot => "specific_lot",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "fifo" => Some(CostBasisMethod::Fifo),
+            "lifo" => Some(CostBasisMethod::Lifo),
+            "average" => Some(CostBasisMethod::Average),
+            "specific_lot" => Some(CostBasisMethod::SpecificLot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LotSelection {
+    lot_id: i32,
+    quantity: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortfolioTransaction {
+    pub(crate) id: Option<i32>,
+    pub(crate) symbol: String,
+    pub(crate) side: String,
+    pub(crate) quantity: f64,
+    pub(crate) price: f64,
+    #[serde(default)]
+    pub(crate) fees: f64,
+    #[serde(default = "default_currency")]
+    pub(crate) currency: String,
+    pub(crate) trade_date: String,
+    #[serde(default)]
+    pub(crate) cost_basis_method: Option<CostBasisMethod>,
+    #[serde(default)]
+    pub(crate) lot_selections: Vec<LotSelection>,
+    #[serde(default)]
+    pub(crate) notes: String,
+}
+
+pub fn default_currency() -> String {
+    "USD".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaxLot {
+    id: i32,
+    symbol: String,
+    transaction_id: Option<i32>,
+    /// For opening lots with an unknown acquisition date, the day tracking started.
+    acquired_date: String,
+    acquired_date_unknown: bool,
+    quantity: f64,
+    remaining_quantity: f64,
+    cost_per_share: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RealizedGain {
+    id: i32,
+    transaction_id: i32,
+    lot_id: i32,
+    symbol: String,
+    quantity: f64,
+    acquired_date: String,
+    disposed_date: String,
+    proceeds: f64,
+    cost_basis: f64,
+    gain: f64,
+    term: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RealizedGainsReport {
+    entries: Vec<RealizedGain>,
+    short_term_gain: f64,
+    long_term_gain: f64,
+    /// Gains on opening lots whose acquisition date was never supplied.
+    unknown_term_gain: f64,
+    total_proceeds: f64,
+    total_cost_basis: f64,
+    total_gain: f64,
+}
+
+// Quantities below this are treated as zero to absorb float noise.
+const EPSILON: f64 = 1e-9;
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    let had_lots: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'tax_lots'",
+        [],
+        |row| row.get(0),
+    )?;
+    let had_unknown_flag: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('tax_lots') WHERE name = 'acquired_date_unknown'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS portfolio_transactions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            side TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            price REAL NOT NULL,
+            fees REAL DEFAULT 0,
+            currency TEXT DEFAULT 'USD',
+            trade_date TEXT NOT NULL,
+            cost_basis_method TEXT,
+            notes TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "portfolio_transactions", "currency", "TEXT DEFAULT 'USD'")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tax_lots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            transaction_id INTEGER,
+            acquired_date TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            remaining_quantity REAL NOT NULL,
+            cost_per_share REAL NOT NULL,
+            acquired_date_unknown INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "tax_lots", "acquired_date_unknown", "INTEGER NOT NULL DEFAULT 0")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS realized_gains (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            transaction_id INTEGER NOT NULL,
+            lot_id INTEGER NOT NULL,
+            symbol TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            acquired_date TEXT NOT NULL,
+            disposed_date TEXT NOT NULL,
+            proceeds REAL NOT NULL,
+            cost_basis REAL NOT NULL,
+            gain REAL NOT NULL,
+            term TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    if !had_lots {
+        // Positions entered before lot tracking existed get a single opening lot so that later
+        // sells have something to match against. Their real acquisition date is unknown.
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        conn.execute(
+            "INSERT INTO tax_lots (symbol, transaction_id, acquired_date, quantity, remaining_quantity, cost_per_share, acquired_date_unknown)
+             SELECT symbol, NULL, ?, shares, shares, avg_price, 1 FROM portfolio WHERE shares > 0",
+            params![today],
+        )?;
+    } else if !had_unknown_flag {
+        // Opening lots seeded by earlier versions were dated on the day of the seed
+        conn.execute("UPDATE tax_lots SET acquired_date_unknown = 1 WHERE transaction_id IS NULL", [])?;
+        conn.execute(
+            "UPDATE realized_gains SET term = 'unknown' WHERE lot_id IN (SELECT id FROM tax_lots WHERE transaction_id IS NULL)",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn normalize_symbol(symbol: &str) -> String {
+    symbol.trim().to_uppercase()
+}
+
+pub fn parse_trade_date(date: &str) -> Result<NaiveDate, String> {
+    let day = date.get(..10).unwrap_or(date);
+    NaiveDate::parse_from_str(day, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid trade date '{}', expected YYYY-MM-DD", date))
+}
+
+/// A lot counts as long-term when it was held for more than one year.
+fn holding_term(acquired: NaiveDate, disposed: NaiveDate) -> &'static str {
+    match acquired.checked_add_months(Months::new(12)) {
+        Some(one_year) if disposed > one_year => "long",
+        _ => "short",
+    }
+}
+
+/// Lots of `symbol` with shares left that were already held on `trade_day`, oldest first.
+/// Opening lots of unknown age count as held on any day and sort before everything else.
+fn load_open_lots(conn: &Connection, symbol: &str, trade_day: &str) -> Result<Vec<TaxLot>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, transaction_id, acquired_date, quantity, remaining_quantity, cost_per_share, acquired_date_unknown
+         FROM tax_lots WHERE symbol = ? AND remaining_quantity > 0 AND (acquired_date <= ? OR acquired_date_unknown)
+         ORDER BY acquired_date_unknown DESC, acquired_date, id",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let lots = stmt.query_map(params![symbol, trade_day], map_lot)
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for lot in lots {
+        result.push(lot.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+fn map_lot(row: &rusqlite::Row) -> SqliteResult<TaxLot> {
+    Ok(TaxLot {
+        id: row.get(0)?,
+        symbol: row.get(1)?,
+        transaction_id: row.get(2)?,
+        acquired_date: row.get(3)?,
+        quantity: row.get(4)?,
+        remaining_quantity: row.get(5)?,
+        cost_per_share: row.get(6)?,
+        acquired_date_unknown: row.get(7)?,
+    })
+}
+
+/// Picks `(lot index, quantity)` pairs from `lots` to cover a sale of `quantity` shares.
+fn select_lots(
+    lots: &[TaxLot],
+    quantity: f64,
+    method: CostBasisMethod,
+    selections: &[LotSelection],
+) -> Result<Vec<(usize, f64)>, String> {
+    let mut picks = Vec::new();
+
+    if method == CostBasisMethod::SpecificLot {
+        for selection in selections {
+            if selection.quantity <= EPSILON {
+                return Err(format!("The quantity taken from lot {} must be greater than zero", selection.lot_id));
+            }
+            let index = lots.iter().position(|lot| lot.id == selection.lot_id)
+                .ok_or_else(|| format!("Lot {} is not an open lot for this symbol on the trade date", selection.lot_id))?;
+            // A lot listed twice is one pick of the combined quantity
+            match picks.iter_mut().find(|(picked, _)| *picked == index) {
+                Some((_, taken)) => *taken += selection.quantity,
+                None => picks.push((index, selection.quantity)),
+            }
+        }
+        for &(index, taken) in &picks {
+            if taken > lots[index].remaining_quantity + EPSILON {
+                return Err(format!(
+                    "Lot {} only has {} shares remaining",
+                    lots[index].id, lots[index].remaining_quantity
+                ));
+            }
+        }
+        let selected: f64 = picks.iter().map(|(_, taken)| taken).sum();
+        if (selected - quantity).abs() > EPSILON {
+            return Err(format!("Selected lots cover {} shares but {} are being sold", selected, quantity));
+        }
+        return Ok(picks);
+    }
+
+    // Average cost still consumes lots oldest-first so holding periods stay accurate.
+    let order: Vec<usize> = if method == CostBasisMethod::Lifo {
+        (0..lots.len()).rev().collect()
+    } else {
+        (0..lots.len()).collect()
+    };
+
+    let mut remaining = quantity;
+    for index in order {
+        if remaining <= EPSILON {
+            break;
+        }
+        let take = remaining.min(lots[index].remaining_quantity);
+        picks.push((index, take));
+        remaining -= take;
+    }
+
+    Ok(picks)
+}
+
+/// Records a buy or sell in its own database transaction.
+pub fn apply_transaction(conn: &Connection, transaction: &PortfolioTransaction) -> Result<i32, String> {
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let transaction_id = insert_transaction(&tx, transaction)?;
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    Ok(transaction_id)
+}
+
+/// Writes a buy or sell into the ledger without managing a database transaction, so
+/// callers can batch several. Buys open a new tax lot; sells consume lots according to
+/// the chosen cost-basis method and write one realized gain entry per lot touched.
+pub fn insert_transaction(tx: &Connection, transaction: &PortfolioTransaction) -> Result<i32, String> {
+    let symbol = normalize_symbol(&transaction.symbol);
+    let side = transaction.side.trim().to_lowercase();
+    let trade_date = parse_trade_date(&transaction.trade_date)?;
+    let trade_day = trade_date.format("%Y-%m-%d").to_string();
+
+    if symbol.is_empty() {
+        return Err("Symbol is required".to_string());
+    }
+    if transaction.quantity <= 0.0 {
+        return Err("Quantity must be greater than zero".to_string());
+    }
+    if transaction.price < 0.0 || transaction.fees < 0.0 {
+        return Err("Price and fees cannot be negative".to_string());
+    }
+    if side != "buy" && side != "sell" {
+        return Err(format!("Unknown transaction side '{}', expected buy or sell", transaction.side));
+    }
+
+    let method = transaction.cost_basis_method.unwrap_or(CostBasisMethod::Fifo);
+    let now = chrono::Utc::now().to_rfc3339();
+
+    tx.execute(
+        "INSERT INTO portfolio_transactions (symbol, side, quantity, price, fees, currency, trade_date, cost_basis_method, notes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            symbol,
+            side,
+            transaction.quantity,
+            transaction.price,
+            transaction.fees,
+            transaction.currency,
+            trade_day,
+            if side == "sell" { Some(method.as_str()) } else { None },
+            transaction.notes,
+            now
+        ],
+    ).map_err(|e| format!("Database error: {}", e))?;
+    let transaction_id = tx.last_insert_rowid() as i32;
+
+    if side == "buy" {
+        let cost_per_share = (transaction.quantity * transaction.price + transaction.fees) / transaction.quantity;
+        tx.execute(
+            "INSERT INTO tax_lots (symbol, transaction_id, acquired_date, quantity, remaining_quantity, cost_per_share) VALUES (?, ?, ?, ?, ?, ?)",
+            params![symbol, transaction_id, trade_day, transaction.quantity, transaction.quantity, cost_per_share],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    } else {
+        let lots = load_open_lots(tx, &symbol, &trade_day)?;
+        let held: f64 = lots.iter().map(|lot| lot.remaining_quantity).sum();
+        if transaction.quantity > held + EPSILON {
+            return Err(format!("Cannot sell {} shares of {} on {}: only {} held then", transaction.quantity, symbol, trade_day, held));
+        }
+
+        let average_cost = if held > 0.0 {
+            lots.iter().map(|lot| lot.remaining_quantity * lot.cost_per_share).sum::<f64>() / held
+        } else {
+            0.0
+        };
+        let net_proceeds_per_share = (transaction.quantity * transaction.price - transaction.fees) / transaction.quantity;
+
+        for (index, quantity) in select_lots(&lots, transaction.quantity, method, &transaction.lot_selections)? {
+            let lot = &lots[index];
+            let cost_per_share = if method == CostBasisMethod::Average { average_cost } else { lot.cost_per_share };
+            let proceeds = quantity * net_proceeds_per_share;
+            let cost_basis = quantity * cost_per_share;
+            let term = if lot.acquired_date_unknown {
+                "unknown"
+            } else {
+                holding_term(parse_trade_date(&lot.acquired_date)?, trade_date)
+            };
+
+            tx.execute(
+                "UPDATE tax_lots SET remaining_quantity = MAX(remaining_quantity - ?, 0) WHERE id = ?",
+                params![quantity, lot.id],
+            ).map_err(|e| format!("Database error: {}", e))?;
+
+            tx.execute(
+                "INSERT INTO realized_gains (transaction_id, lot_id, symbol, quantity, acquired_date, disposed_date, proceeds, cost_basis, gain, term) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params![transaction_id, lot.id, symbol, quantity, lot.acquired_date, trade_day, proceeds, cost_basis, proceeds - cost_basis, term],
+            ).map_err(|e| format!("Database error: {}", e))?;
+        }
+
+        // Under average cost every share held on the trade date carries the pooled basis; lots
+        // bought after a backdated sale keep their own
+        if method == CostBasisMethod::Average {
+            let ids: Vec<String> = lots.iter().map(|lot| lot.id.to_string()).collect();
+            tx.execute(
+                &format!("UPDATE tax_lots SET cost_per_share = ? WHERE id IN ({}) AND remaining_quantity > 0", ids.join(", ")),
+                params![average_cost],
+            ).map_err(|e| format!("Database error: {}", e))?;
+        }
+    }
+
+    sync_position(tx, &symbol, transaction.price)?;
+
+    Ok(transaction_id)
+}
+
+/// Moves a symbol's ledger (transactions, lots and realized gains) over to `new_symbol`.
+pub fn rename_symbol(conn: &Connection, symbol: &str, new_symbol: &str) -> Result<(), String> {
+    for table in ["portfolio_transactions", "tax_lots", "realized_gains"] {
+        conn.execute(&format!("UPDATE {} SET symbol = ? WHERE symbol = ?", table), params![new_symbol, symbol])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Rewrites the `portfolio` row for `symbol` from its open lots, creating it if needed.
+pub fn sync_position(conn: &Connection, symbol: &str, last_price: f64) -> Result<(), String> {
+    let (shares, cost): (f64, f64) = conn.query_row(
+        "SELECT COALESCE(SUM(remaining_quantity), 0), COALESCE(SUM(remaining_quantity * cost_per_share), 0) FROM tax_lots WHERE symbol = ?",
+        params![symbol],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let avg_price = if shares > EPSILON { cost / shares } else { 0.0 };
+
+    let existing: Option<(i32, f64)> = conn.query_row(
+        "SELECT id, current_price FROM portfolio WHERE symbol = ? ORDER BY id LIMIT 1",
+        params![symbol],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).ok();
+
+    let current_price = match existing {
+        Some((_, price)) if price > 0.0 => price,
+        _ => last_price,
+    };
+    let total_value = shares * current_price;
+    let gain_loss = total_value - cost;
+    let gain_loss_percent = if cost > 0.0 { gain_loss / cost * 100.0 } else { 0.0 };
+
+    if let Some((id, _)) = existing {
+        // Buying back into a closed or trashed position reopens it.
+        conn.execute(
+            "UPDATE portfolio SET shares = ?, avg_price = ?, current_price = ?, total_value = ?, gain_loss = ?, gain_loss_percent = ?,
+             closed_at = CASE WHEN ?7 > 0 THEN NULL ELSE closed_at END,
+             deleted_at = CASE WHEN ?7 > 0 THEN NULL ELSE deleted_at END WHERE id = ?8",
+            params![shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent, shares, id],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    } else {
+        conn.execute(
+            "INSERT INTO portfolio (symbol, shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent, notes) VALUES (?, ?, ?, ?, ?, ?, ?, '')",
+            params![symbol, shares, avg_price, current_price, total_value, gain_loss, gain_loss_percent],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Gives a position entered by hand an opening lot so later sells can match against it.
+/// When the shares were bought isn't known, so the lot is flagged until the user supplies it.
+pub fn open_position_lot(conn: &Connection, symbol: &str, shares: f64, cost_per_share: f64) -> Result<(), String> {
+    if shares <= EPSILON {
+        return Ok(());
+    }
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    conn.execute(
+        "INSERT INTO tax_lots (symbol, transaction_id, acquired_date, quantity, remaining_quantity, cost_per_share, acquired_date_unknown) VALUES (?, NULL, ?, ?, ?, ?, 1)",
+        params![normalize_symbol(symbol), today, shares, shares, cost_per_share],
+    ).map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+// Lot tracking commands
+#[tauri::command]
+pub fn record_portfolio_transaction(transaction: PortfolioTransaction) -> Result<i32, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    apply_transaction(conn, &transaction)
+}
+
+/// Records the real acquisition date of an opening lot and reclassifies the gains already taken from it.
+#[tauri::command]
+pub fn set_tax_lot_acquired_date(lot_id: i32, acquired_date: String) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let acquired = parse_trade_date(&acquired_date)?;
+    let acquired_day = acquired.format("%Y-%m-%d").to_string();
+
+    let transaction_id: Option<i32> = conn.query_row(
+        "SELECT transaction_id FROM tax_lots WHERE id = ?",
+        params![lot_id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Tax lot {} not found", lot_id))?;
+    if transaction_id.is_some() {
+        return Err("Only opening lots can be redated; edit the buy transaction instead".to_string());
+    }
+
+    let mut stmt = conn.prepare("SELECT id, disposed_date FROM realized_gains WHERE lot_id = ?")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let disposals = stmt.query_map(params![lot_id], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Database error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+    for (gain_id, disposed_date) in disposals {
+        let disposed = parse_trade_date(&disposed_date)?;
+        if disposed < acquired {
+            return Err(format!("Shares from this lot were already sold on {}", disposed_date));
+        }
+        tx.execute(
+            "UPDATE realized_gains SET acquired_date = ?, term = ? WHERE id = ?",
+            params![acquired_day, holding_term(acquired, disposed), gain_id],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+    tx.execute(
+        "UPDATE tax_lots SET acquired_date = ?, acquired_date_unknown = 0 WHERE id = ?",
+        params![acquired_day, lot_id],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_portfolio_transactions(symbol: Option<String>) -> Result<Vec<PortfolioTransaction>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let symbol = symbol.map(|s| normalize_symbol(&s));
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, side, quantity, price, fees, currency, trade_date, cost_basis_method, notes FROM portfolio_transactions
+         WHERE ?1 IS NULL OR symbol = ?1 ORDER BY trade_date DESC, id DESC",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let transactions = stmt.query_map(params![symbol], |row| {
+        let method: Option<String> = row.get(8)?;
+        Ok(PortfolioTransaction {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            side: row.get(2)?,
+            quantity: row.get(3)?,
+            price: row.get(4)?,
+            fees: row.get(5)?,
+            currency: row.get::<_, Option<String>>(6)?.unwrap_or_else(default_currency),
+            trade_date: row.get(7)?,
+            cost_basis_method: method.as_deref().and_then(CostBasisMethod::parse),
+            lot_selections: Vec::new(),
+            notes: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for transaction in transactions {
+        result.push(transaction.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn get_tax_lots(symbol: Option<String>, include_closed: bool) -> Result<Vec<TaxLot>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let symbol = symbol.map(|s| normalize_symbol(&s));
+    let mut stmt = conn.prepare(
+        "SELECT id, symbol, transaction_id, acquired_date, quantity, remaining_quantity, cost_per_share, acquired_date_unknown FROM tax_lots
+         WHERE (?1 IS NULL OR symbol = ?1) AND (?2 OR remaining_quantity > 0) ORDER BY symbol, acquired_date, id",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let lots = stmt.query_map(params![symbol, include_closed], map_lot)
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for lot in lots {
+        result.push(lot.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn get_realized_gains(start_date: Option<String>, end_date: Option<String>) -> Result<RealizedGainsReport, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, transaction_id, lot_id, symbol, quantity, acquired_date, disposed_date, proceeds, cost_basis, gain, term FROM realized_gains
+         WHERE (?1 IS NULL OR disposed_date >= ?1) AND (?2 IS NULL OR disposed_date <= ?2) ORDER BY disposed_date, id",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let gains = stmt.query_map(params![start_date, end_date], |row| {
+        Ok(RealizedGain {
+            id: row.get(0)?,
+            transaction_id: row.get(1)?,
+            lot_id: row.get(2)?,
+            symbol: row.get(3)?,
+            quantity: row.get(4)?,
+            acquired_date: row.get(5)?,
+            disposed_date: row.get(6)?,
+            proceeds: row.get(7)?,
+            cost_basis: row.get(8)?,
+            gain: row.get(9)?,
+            term: row.get(10)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut entries = Vec::new();
+    for gain in gains {
+        entries.push(gain.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    let short_term_gain = entries.iter().filter(|g| g.term == "short").map(|g| g.gain).sum();
+    let long_term_gain = entries.iter().filter(|g| g.term == "long").map(|g| g.gain).sum();
+    let unknown_term_gain = entries.iter().filter(|g| g.term == "unknown").map(|g| g.gain).sum();
+    let total_proceeds = entries.iter().map(|g| g.proceeds).sum();
+    let total_cost_basis = entries.iter().map(|g| g.cost_basis).sum();
+
+    Ok(RealizedGainsReport {
+        entries,
+        short_term_gain,
+        long_term_gain,
+        unknown_term_gain,
+        total_proceeds,
+        total_cost_basis,
+        total_gain: short_term_gain + long_term_gain + unknown_term_gain,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot(id: i32, acquired_date: &str, remaining_quantity: f64, cost_per_share: f64) -> TaxLot {
+        TaxLot {
+            id,
+            symbol: "ACME".to_string(),
+            transaction_id: Some(id),
+            acquired_date: acquired_date.to_string(),
+            acquired_date_unknown: false,
+            quantity: remaining_quantity,
+            remaining_quantity,
+            cost_per_share,
+        }
+    }
+
+    fn open_lots() -> Vec<TaxLot> {
+        vec![lot(1, "2023-01-10", 10.0, 100.0), lot(2, "2023-06-01", 5.0, 120.0), lot(3, "2024-02-01", 8.0, 90.0)]
+    }
+
+    fn trade(side: &str, quantity: f64, price: f64, trade_date: &str, method: CostBasisMethod) -> PortfolioTransaction {
+        PortfolioTransaction {
+            id: None,
+            symbol: "acme".to_string(),
+            side: side.to_string(),
+            quantity,
+            price,
+            fees: 0.0,
+            currency: default_currency(),
+            trade_date: trade_date.to_string(),
+            cost_basis_method: Some(method),
+            lot_selections: Vec::new(),
+            notes: String::new(),
+        }
+    }
+
+    fn gains(conn: &Connection) -> Vec<(i32, f64, f64, String)> {
+        let mut stmt = conn.prepare("SELECT lot_id, quantity, cost_basis, term FROM realized_gains ORDER BY id").unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn fifo_takes_oldest_lots_first() {
+        let picks = select_lots(&open_lots(), 12.0, CostBasisMethod::Fifo, &[]).unwrap();
+        assert_eq!(picks, vec![(0, 10.0), (1, 2.0)]);
+    }
+
+    #[test]
+    fn lifo_takes_newest_lots_first() {
+        let picks = select_lots(&open_lots(), 12.0, CostBasisMethod::Lifo, &[]).unwrap();
+        assert_eq!(picks, vec![(2, 8.0), (1, 4.0)]);
+    }
+
+    #[test]
+    fn average_consumes_lots_oldest_first() {
+        let picks = select_lots(&open_lots(), 3.0, CostBasisMethod::Average, &[]).unwrap();
+        assert_eq!(picks, vec![(0, 3.0)]);
+    }
+
+    #[test]
+    fn specific_lot_uses_the_selected_lots() {
+        let selections = [LotSelection { lot_id: 3, quantity: 4.0 }, LotSelection { lot_id: 2, quantity: 1.0 }];
+        let picks = select_lots(&open_lots(), 5.0, CostBasisMethod::SpecificLot, &selections).unwrap();
+        assert_eq!(picks, vec![(2, 4.0), (1, 1.0)]);
+    }
+
+    #[test]
+    fn specific_lot_rejects_bad_selections() {
+        let over = [LotSelection { lot_id: 2, quantity: 6.0 }];
+        assert!(select_lots(&open_lots(), 6.0, CostBasisMethod::SpecificLot, &over).is_err());
+
+        let short = [LotSelection { lot_id: 1, quantity: 2.0 }];
+        assert!(select_lots(&open_lots(), 3.0, CostBasisMethod::SpecificLot, &short).is_err());
+
+        let missing = [LotSelection { lot_id: 9, quantity: 1.0 }];
+        assert!(select_lots(&open_lots(), 1.0, CostBasisMethod::SpecificLot, &missing).is_err());
+
+        // A negative pick would otherwise add shares back to lot 2
+        let negative = [LotSelection { lot_id: 1, quantity: 8.0 }, LotSelection { lot_id: 2, quantity: -3.0 }];
+        assert!(select_lots(&open_lots(), 5.0, CostBasisMethod::SpecificLot, &negative).is_err());
+        let zero = [LotSelection { lot_id: 1, quantity: 3.0 }, LotSelection { lot_id: 2, quantity: 0.0 }];
+        assert!(select_lots(&open_lots(), 3.0, CostBasisMethod::SpecificLot, &zero).is_err());
+    }
+
+    #[test]
+    fn specific_lot_adds_up_repeated_lots() {
+        let twice = [LotSelection { lot_id: 2, quantity: 3.0 }, LotSelection { lot_id: 2, quantity: 3.0 }];
+        assert!(select_lots(&open_lots(), 6.0, CostBasisMethod::SpecificLot, &twice).is_err());
+
+        let twice = [LotSelection { lot_id: 2, quantity: 2.0 }, LotSelection { lot_id: 1, quantity: 1.0 }, LotSelection { lot_id: 2, quantity: 3.0 }];
+        let picks = select_lots(&open_lots(), 6.0, CostBasisMethod::SpecificLot, &twice).unwrap();
+        assert_eq!(picks, vec![(1, 5.0), (0, 1.0)]);
+    }
+
+    #[test]
+    fn holding_term_needs_more_than_a_year() {
+        let acquired = NaiveDate::from_ymd_opt(2023, 3, 15).unwrap();
+        assert_eq!(holding_term(acquired, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()), "short");
+        assert_eq!(holding_term(acquired, NaiveDate::from_ymd_opt(2024, 3, 16).unwrap()), "long");
+    }
+
+    #[test]
+    fn average_cost_pools_the_basis() {
+        let conn = crate::test_support::db();
+        insert_transaction(&conn, &trade("buy", 10.0, 100.0, "2023-01-10", CostBasisMethod::Fifo)).unwrap();
+        insert_transaction(&conn, &trade("buy", 10.0, 200.0, "2023-02-10", CostBasisMethod::Fifo)).unwrap();
+        insert_transaction(&conn, &trade("sell", 5.0, 180.0, "2023-03-10", CostBasisMethod::Average)).unwrap();
+
+        assert_eq!(gains(&conn), vec![(1, 5.0, 750.0, "short".to_string())]);
+        let (shares, avg_price): (f64, f64) = conn
+            .query_row("SELECT shares, avg_price FROM portfolio WHERE symbol = 'ACME'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((shares, avg_price), (15.0, 150.0));
+    }
+
+    #[test]
+    fn backdated_average_sell_leaves_later_lots_at_their_cost() {
+        let conn = crate::test_support::db();
+        insert_transaction(&conn, &trade("buy", 10.0, 100.0, "2023-01-10", CostBasisMethod::Fifo)).unwrap();
+        insert_transaction(&conn, &trade("buy", 10.0, 200.0, "2023-02-10", CostBasisMethod::Fifo)).unwrap();
+        insert_transaction(&conn, &trade("buy", 10.0, 400.0, "2023-05-10", CostBasisMethod::Fifo)).unwrap();
+        insert_transaction(&conn, &trade("sell", 5.0, 180.0, "2023-03-10", CostBasisMethod::Average)).unwrap();
+
+        assert_eq!(gains(&conn), vec![(1, 5.0, 750.0, "short".to_string())]);
+        let mut stmt = conn.prepare("SELECT remaining_quantity, cost_per_share FROM tax_lots ORDER BY id").unwrap();
+        let lots: Vec<(f64, f64)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lots, vec![(5.0, 150.0), (10.0, 150.0), (10.0, 400.0)]);
+    }
+
+    #[test]
+    fn backdated_sell_ignores_later_lots() {
+        let conn = crate::test_support::db();
+        insert_transaction(&conn, &trade("buy", 10.0, 100.0, "2022-01-10", CostBasisMethod::Fifo)).unwrap();
+        insert_transaction(&conn, &trade("buy", 10.0, 150.0, "2024-01-10", CostBasisMethod::Fifo)).unwrap();
+
+        // LIFO would reach for the 2024 lot, but it wasn't held yet
+        insert_transaction(&conn, &trade("sell", 4.0, 120.0, "2023-06-01", CostBasisMethod::Lifo)).unwrap();
+        assert_eq!(gains(&conn), vec![(1, 4.0, 400.0, "long".to_string())]);
+
+        let error = insert_transaction(&conn, &trade("sell", 8.0, 120.0, "2023-07-01", CostBasisMethod::Fifo)).unwrap_err();
+        assert!(error.contains("only 6 held"), "{}", error);
+    }
+
+    #[test]
+    fn opening_lots_report_an_unknown_term() {
+        let conn = crate::test_support::db();
+        open_position_lot(&conn, "acme", 10.0, 50.0).unwrap();
+        insert_transaction(&conn, &trade("sell", 2.0, 60.0, "2020-01-01", CostBasisMethod::Fifo)).unwrap();
+
+        assert_eq!(gains(&conn), vec![(1, 2.0, 100.0, "unknown".to_string())]);
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::DB;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutlineHeading {
+    level: u8,
+    text: String,
+    /// Matches the `id` attribute on the rendered heading.
+    anchor: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    text: String,
+    checked: bool,
+    /// 1-based line of the item in the note's Markdown source.
+    line: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderedNote {
+    id: i32,
+    title: String,
+    html: String,
+    outline: Vec<OutlineHeading>,
+    checklist: Vec<ChecklistItem>,
+    tasks_total: usize,
+    tasks_done: usize,
+}
+
+pub struct RenderedMarkdown {
+    pub html: String,
+    pub outline: Vec<OutlineHeading>,
+    pub checklist: Vec<ChecklistItem>,
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// GitHub-style anchor: lowercase words joined by dashes, numbered when repeated.
+fn slugify(text: &str, used: &mut HashMap<String, usize>) -> String {
+    let mut slug = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' {
+            slug.push(c);
+        } else if c.is_whitespace() {
+            slug.push('-');
+        }
+    }
+    if slug.is_empty() {
+        slug.push_str("section");
+    }
+
+    let count = used.entry(slug.clone()).or_insert(0);
+    *count += 1;
+    if *count > 1 {
+        format!("{}-{}", slug, *count - 1)
+    } else {
+        slug
+    }
+}
+
+fn sanitize(html: &str) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder.add_tags(["input"]);
+    builder.add_tag_attributes("input", ["type", "checked", "disabled"]);
+    builder.add_tag_attributes("th", ["style"]);
+    builder.add_tag_attributes("td", ["style"]);
+    for heading in ["h1", "h2", "h3", "h4", "h5", "h6"] {
+        builder.add_tag_attributes(heading, ["id"]);
+    }
+    // Only task-list checkboxes and table alignment survive from those extra attributes.
+    builder.attribute_filter(|element, attribute, value| match (element, attribute) {
+        ("input", "type") if value != "checkbox" => None,
+        ("th" | "td", "style") if !matches!(value, "text-align: left" | "text-align: center" | "text-align: right") => None,
+        _ => Some(value.into()),
+    });
+    builder.clean(html).to_string()
+}
+
+/// Renders CommonMark with GFM tables, task lists and strikethrough to sanitized HTML.
+pub fn render_markdown(source: &str) -> RenderedMarkdown {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |offset: usize| line_starts.partition_point(|start| *start <= offset);
+
+    // First pass collects heading text and task items; anchors are then attached to the events.
+    let events: Vec<(Event, std::ops::Range<usize>)> = Parser::new_ext(source, options).into_offset_iter().collect();
+
+    let mut outline = Vec::new();
+    let mut checklist = Vec::new();
+    let mut used_anchors = HashMap::new();
+    let mut heading: Option<(u8, String, usize)> = None;
+    let mut task: Option<(bool, String, usize)> = None;
+    let mut anchors: HashMap<usize, String> = HashMap::new();
+    let mut item_start = 0;
+
+    for (index, (event, range)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((heading_level(*level), String::new(), index));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text, start)) = heading.take() {
+                    let anchor = slugify(&text, &mut used_anchors);
+                    anchors.insert(start, anchor.clone());
+                    outline.push(OutlineHeading { level, text: text.trim().to_string(), anchor });
+                }
+            }
+            Event::Start(Tag::Item) => {
+                // A nested item ends the parent's task text
+                if let Some((checked, text, line)) = task.take() {
+                    checklist.push(ChecklistItem { text: text.trim().to_string(), checked, line });
+                }
+                item_start = range.start;
+            }
+            Event::TaskListMarker(checked) => task = Some((*checked, String::new(), line_of(item_start))),
+            Event::End(TagEnd::Item) => {
+                if let Some((checked, text, line)) = task.take() {
+                    checklist.push(ChecklistItem { text: text.trim().to_string(), checked, line });
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, buffer, _)) = heading.as_mut() {
+                    buffer.push_str(text);
+                }
+                if let Some((_, buffer, _)) = task.as_mut() {
+                    buffer.push_str(text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((_, buffer, _)) = task.as_mut() {
+                    buffer.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let events = events.into_iter().enumerate().map(|(index, (event, _))| match (event, anchors.remove(&index)) {
+        (Event::Start(Tag::Heading { level, classes, attrs, .. }), Some(anchor)) => Event::Start(Tag::Heading {
+            level,
+            id: Some(CowStr::from(anchor)),
+            classes,
+            attrs,
+        }),
+        (event, _) => event,
+    });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+
+    RenderedMarkdown { html: sanitize(&html), outline, checklist }
+}
+
+// Markdown commands
+#[tauri::command]
+pub fn render_note(id: i32) -> Result<RenderedNote, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let title: String = conn.query_row("SELECT title FROM notes WHERE id = ?", params![id], |row| row.get(0))
+        .map_err(|_| format!("Note {} not found", id))?;
+    let content = crate::vault::read_content(conn, id)?;
+
+    let rendered = render_markdown(&content);
+    Ok(RenderedNote {
+        id,
+        title,
+        html: rendered.html,
+        tasks_total: rendered.checklist.len(),
+        tasks_done: rendered.checklist.iter().filter(|item| item.checked).count(),
+        outline: rendered.outline,
+        checklist: rendered.checklist,
+    })
+}
@@ -0,0 +1,408 @@
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::lots::parse_trade_date;
+use crate::prices::load_price_history;
+use crate::DB;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PerformanceRange {
+    start_date: Option<String>,
+    end_date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValuationPoint {
+    date: String,
+    value: f64,
+    net_cash_flow: f64,
+    cumulative_return: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PositionContribution {
+    symbol: String,
+    start_value: f64,
+    end_value: f64,
+    net_cash_flow: f64,
+    gain: f64,
+    contribution: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortfolioPerformance {
+    start_date: String,
+    end_date: String,
+    start_value: f64,
+    end_value: f64,
+    net_cash_flow: f64,
+    time_weighted_return: f64,
+    money_weighted_return: Option<f64>,
+    series: Vec<ValuationPoint>,
+    contributions: Vec<PositionContribution>,
+}
+
+/// A buy or sell reduced to what the valuation needs. `amount` is the cash that went
+/// into the position: positive for buys, negative for sell proceeds.
+struct Trade {
+    symbol: usize,
+    date: NaiveDate,
+    quantity: f64,
+    amount: f64,
+    price: f64,
+}
+
+#[derive(Default)]
+struct PositionState {
+    quantity: f64,
+    price: f64,
+    marks: Vec<(NaiveDate, f64)>,
+    next_mark: usize,
+    start_value: f64,
+    value: f64,
+    net_flow: f64,
+    weighted_flow: f64,
+}
+
+impl PositionState {
+    /// Moves the mark pointer forward so `price` is the last known close on or before `day`.
+    fn mark_to(&mut self, day: NaiveDate) {
+        while self.next_mark < self.marks.len() && self.marks[self.next_mark].0 <= day {
+            self.price = self.marks[self.next_mark].1;
+            self.next_mark += 1;
+        }
+        self.value = self.quantity * self.price;
+    }
+}
+
+fn load_trades(conn: &Connection, end_date: &str, symbols: &mut Vec<String>) -> Result<Vec<Trade>, String> {
+    // Opening lots stand in for the buys that happened before lot tracking existed.
+    let mut stmt = conn.prepare(
+        "SELECT symbol, side, quantity, price, fees, trade_date FROM portfolio_transactions WHERE trade_date <= ?1
+         UNION ALL
+         SELECT symbol, 'buy', quantity, cost_per_share, 0, acquired_date FROM tax_lots WHERE transaction_id IS NULL AND acquired_date <= ?1
+         ORDER BY 6",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let rows = stmt.query_map(params![end_date], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(2)?,
+            row.get::<_, f64>(3)?,
+            row.get::<_, f64>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut trades = Vec::new();
+    for row in rows {
+        let (symbol, side, quantity, price, fees, date) = row.map_err(|e| format!("Database error: {}", e))?;
+        let index = match symbols.iter().position(|s| *s == symbol) {
+            Some(index) => index,
+            None => {
+                symbols.push(symbol);
+                symbols.len() - 1
+            }
+        };
+        let (quantity, amount) = if side == "sell" {
+            (-quantity, -(quantity * price - fees))
+        } else {
+            (quantity, quantity * price + fees)
+        };
+        trades.push(Trade { symbol: index, date: parse_trade_date(&date)?, quantity, amount, price });
+    }
+
+    Ok(trades)
+}
+
+/// Annualized internal rate of return for dated cash flows, or `None` when it has no root.
+fn xirr(flows: &[(NaiveDate, f64)]) -> Option<f64> {
+    let first = flows.first()?.0;
+    let has_inflow = flows.iter().any(|(_, amount)| *amount > 0.0);
+    let has_outflow = flows.iter().any(|(_, amount)| *amount < 0.0);
+    if !has_inflow || !has_outflow {
+        return None;
+    }
+
+    let years: Vec<f64> = flows.iter().map(|(date, _)| (*date - first).num_days() as f64 / 365.0).collect();
+    let npv = |rate: f64| -> f64 {
+        flows.iter().zip(&years).map(|((_, amount), t)| amount / (1.0 + rate).powf(*t)).sum()
+    };
+    let derivative = |rate: f64| -> f64 {
+        flows.iter().zip(&years).map(|((_, amount), t)| -t * amount / (1.0 + rate).powf(t + 1.0)).sum()
+    };
+
+    // Newton's method converges quickly for typical portfolios.
+    let mut rate = 0.1;
+    for _ in 0..100 {
+        let value = npv(rate);
+        if value.abs() < 1e-7 {
+            return Some(rate);
+        }
+        let slope = derivative(rate);
+        if slope == 0.0 || !slope.is_finite() {
+            break;
+        }
+        let next = rate - value / slope;
+        if !next.is_finite() || next <= -1.0 {
+            break;
+        }
+        rate = next;
+    }
+
+    // Fall back to bisection when Newton wanders off.
+    let (mut low, mut high) = (-0.9999, 100.0);
+    if npv(low).signum() == npv(high).signum() {
+        return None;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if npv(mid).signum() == npv(low).signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+pub fn compute_performance(conn: &Connection, range: &PerformanceRange) -> Result<PortfolioPerformance, String> {
+    let end = match &range.end_date {
+        Some(date) => parse_trade_date(date)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let end_day = end.format("%Y-%m-%d").to_string();
+
+    let mut symbols = Vec::new();
+    let trades = load_trades(conn, &end_day, &mut symbols)?;
+
+    let start = match &range.start_date {
+        Some(date) => parse_trade_date(date)?,
+        None => trades.first().map(|t| t.date).unwrap_or(end),
+    };
+    if start > end {
+        return Err("Start date must not be after end date".to_string());
+    }
+
+    let mut positions: Vec<PositionState> = Vec::new();
+    for (index, symbol) in symbols.iter().enumerate() {
+        let mut marks = Vec::new();
+        for trade in trades.iter().filter(|t| t.symbol == index) {
+            marks.push((trade.date, trade.price));
+        }
+        // Stored closes come after trade prices so they win on the same day.
+        for (date, close) in load_price_history(conn, symbol, &end_day)? {
+            marks.push((parse_trade_date(&date)?, close));
+        }
+        marks.sort_by_key(|(date, _)| *date);
+        positions.push(PositionState { marks, ..Default::default() });
+    }
+
+    // Roll every trade before the range into the opening positions.
+    let mut next_trade = 0;
+    while next_trade < trades.len() && trades[next_trade].date < start {
+        positions[trades[next_trade].symbol].quantity += trades[next_trade].quantity;
+        next_trade += 1;
+    }
+    let baseline_day = start.pred_opt().unwrap_or(start);
+    for position in positions.iter_mut() {
+        position.mark_to(baseline_day);
+        position.start_value = position.value;
+    }
+    let start_value: f64 = positions.iter().map(|p| p.value).sum();
+
+    let period_days = ((end - start).num_days() + 1) as f64;
+    let mut series = Vec::new();
+    let mut cash_flows = vec![(start, -start_value)];
+    let mut previous_value = start_value;
+    let mut growth = 1.0;
+
+    let mut day = start;
+    while day <= end {
+        let mut day_flow = 0.0;
+        while next_trade < trades.len() && trades[next_trade].date == day {
+            let trade = &trades[next_trade];
+            let position = &mut positions[trade.symbol];
+            position.quantity += trade.quantity;
+            position.net_flow += trade.amount;
+            position.weighted_flow += trade.amount * (end - day).num_days() as f64 / period_days;
+            day_flow += trade.amount;
+            next_trade += 1;
+        }
+
+        for position in positions.iter_mut() {
+            position.mark_to(day);
+        }
+        let value: f64 = positions.iter().map(|p| p.value).sum();
+
+        // Flows land at the end of the day, so the day's return excludes them.
+        if previous_value > 1e-9 {
+            growth *= (value - day_flow) / previous_value;
+        }
+        previous_value = value;
+
+        if day_flow != 0.0 {
+            cash_flows.push((day, -day_flow));
+        }
+        series.push(ValuationPoint {
+            date: day.format("%Y-%m-%d").to_string(),
+            value,
+            net_cash_flow: day_flow,
+            cumulative_return: growth - 1.0,
+        });
+
+        day = match day.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    let end_value: f64 = positions.iter().map(|p| p.value).sum();
+    cash_flows.push((end, end_value));
+
+    // Contributions use the Modified Dietz denominator so they sum to the period return.
+    let capital = start_value + positions.iter().map(|p| p.weighted_flow).sum::<f64>();
+    let mut contributions: Vec<PositionContribution> = symbols.iter().zip(&positions)
+        .filter(|(_, p)| p.start_value != 0.0 || p.value != 0.0 || p.net_flow != 0.0)
+        .map(|(symbol, p)| {
+            let gain = p.value - p.start_value - p.net_flow;
+            PositionContribution {
+                symbol: symbol.clone(),
+                start_value: p.start_value,
+                end_value: p.value,
+                net_cash_flow: p.net_flow,
+                gain,
+                contribution: if capital > 1e-9 { gain / capital } else { 0.0 },
+            }
+        })
+        .collect();
+    contributions.sort_by(|a, b| b.contribution.total_cmp(&a.contribution));
+
+    Ok(PortfolioPerformance {
+        start_date: start.format("%Y-%m-%d").to_string(),
+        end_date: end_day,
+        start_value,
+        end_value,
+        net_cash_flow: positions.iter().map(|p| p.net_flow).sum(),
+        time_weighted_return: growth - 1.0,
+        money_weighted_return: xirr(&cash_flows),
+        series,
+        contributions,
+    })
+}
+
+// Performance commands
+#[tauri::command]
+pub fn portfolio_performance(range: Option<PerformanceRange>) -> Result<PortfolioPerformance, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    compute_performance(conn, &range.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        parse_trade_date(text).unwrap()
+    }
+
+    fn buy(conn: &Connection, symbol: &str, quantity: f64, price: f64, trade_date: &str) {
+        conn.execute(
+            "INSERT INTO portfolio_transactions (symbol, side, quantity, price, fees, trade_date, created_at) VALUES (?, 'buy', ?, ?, 0, ?, ?)",
+            params![symbol, quantity, price, trade_date, trade_date],
+        ).unwrap();
+    }
+
+    fn close(conn: &Connection, symbol: &str, date: &str, close: f64) {
+        conn.execute("INSERT INTO price_history (symbol, date, close) VALUES (?, ?, ?)", params![symbol, date, close]).unwrap();
+    }
+
+    fn range(start_date: &str, end_date: &str) -> PerformanceRange {
+        PerformanceRange { start_date: Some(start_date.to_string()), end_date: Some(end_date.to_string()) }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn xirr_finds_the_annual_rate_with_newton() {
+        let rate = xirr(&[(date("2023-01-01"), -100.0), (date("2024-01-01"), 110.0)]).unwrap();
+        assert_close(rate, 0.1);
+
+        // Money added halfway through earns for half the year
+        let flows = [(date("2023-01-01"), -100.0), (date("2023-07-02"), -100.0), (date("2024-01-01"), 215.0)];
+        let rate = xirr(&flows).unwrap();
+        assert!(rate > 0.09 && rate < 0.11, "{}", rate);
+        let npv: f64 = flows.iter()
+            .map(|(day, amount)| amount / (1.0 + rate).powf((*day - flows[0].0).num_days() as f64 / 365.0))
+            .sum();
+        assert!(npv.abs() < 1e-6);
+    }
+
+    #[test]
+    fn xirr_falls_back_to_bisection_for_near_total_losses() {
+        // Newton's first step from 10% lands below -100%, so only bisection finds this root
+        let rate = xirr(&[(date("2023-01-01"), -100.0), (date("2024-01-01"), 1.0)]).unwrap();
+        assert!((rate - -0.99).abs() < 1e-6, "{}", rate);
+    }
+
+    #[test]
+    fn xirr_needs_money_going_both_ways() {
+        assert_eq!(xirr(&[]), None);
+        assert_eq!(xirr(&[(date("2023-01-01"), -100.0), (date("2024-01-01"), -10.0)]), None);
+        assert_eq!(xirr(&[(date("2023-01-01"), 100.0)]), None);
+    }
+
+    #[test]
+    fn time_weighted_return_chains_daily_returns_without_flows() {
+        let conn = crate::test_support::db();
+        buy(&conn, "ACME", 10.0, 10.0, "2024-01-01");
+        close(&conn, "ACME", "2024-01-02", 11.0);
+        // Doubling the position on a day it rose doesn't count as a gain
+        buy(&conn, "ACME", 10.0, 12.0, "2024-01-03");
+        close(&conn, "ACME", "2024-01-03", 12.0);
+        close(&conn, "ACME", "2024-01-04", 6.0);
+
+        let performance = compute_performance(&conn, &range("2024-01-02", "2024-01-04")).unwrap();
+        assert_close(performance.start_value, 100.0);
+        assert_close(performance.end_value, 120.0);
+        assert_close(performance.net_cash_flow, 120.0);
+        let returns: Vec<f64> = performance.series.iter().map(|point| point.cumulative_return).collect();
+        assert_eq!(returns.len(), 3);
+        assert_close(returns[0], 0.1);
+        assert_close(returns[1], 0.2);
+        assert_close(returns[2], -0.4);
+        assert_close(performance.time_weighted_return, -0.4);
+        assert_close(performance.series[1].net_cash_flow, 120.0);
+    }
+
+    #[test]
+    fn contributions_use_modified_dietz_and_sum_to_the_period_return() {
+        let conn = crate::test_support::db();
+        buy(&conn, "ACME", 10.0, 10.0, "2024-01-01");
+        buy(&conn, "BETA", 5.0, 20.0, "2024-01-01");
+        close(&conn, "ACME", "2024-01-02", 11.0);
+        buy(&conn, "ACME", 10.0, 12.0, "2024-01-03");
+        close(&conn, "ACME", "2024-01-04", 6.0);
+        close(&conn, "BETA", "2024-01-04", 25.0);
+
+        let performance = compute_performance(&conn, &range("2024-01-02", "2024-01-04")).unwrap();
+        // The buy on day two of three is weighted by the one day it was invested
+        let capital = 200.0 + 120.0 / 3.0;
+        let symbols: Vec<&str> = performance.contributions.iter().map(|c| c.symbol.as_str()).collect();
+        assert_eq!(symbols, ["BETA", "ACME"]);
+        assert_close(performance.contributions[0].gain, 25.0);
+        assert_close(performance.contributions[0].contribution, 25.0 / capital);
+        assert_close(performance.contributions[1].gain, -100.0);
+        assert_close(performance.contributions[1].contribution, -100.0 / capital);
+
+        let total: f64 = performance.contributions.iter().map(|c| c.contribution).sum();
+        let gain = performance.end_value - performance.start_value - performance.net_cash_flow;
+        assert_close(total, gain / capital);
+    }
+}
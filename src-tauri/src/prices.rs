@@ -0,0 +1,149 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::alerts::{evaluate_alerts, notify_triggered};
+use crate::lots::{normalize_symbol, parse_trade_date};
+use crate::DB;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricePoint {
+    symbol: String,
+    date: String,
+    close: f64,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS price_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            symbol TEXT NOT NULL,
+            date TEXT NOT NULL,
+            close REAL NOT NULL,
+            UNIQUE(symbol, date)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Sets `current_price` on every portfolio row for `symbol` and recomputes the derived columns.
+pub fn apply_current_price(conn: &Connection, symbol: &str, price: f64) -> Result<usize, String> {
+    conn.execute(
+        "UPDATE portfolio SET current_price = ?1,
+            total_value = shares * ?1,
+            gain_loss = (?1 - avg_price) * shares,
+            gain_loss_percent = CASE WHEN avg_price > 0 THEN (?1 - avg_price) / avg_price * 100 ELSE 0 END
+         WHERE symbol = ?2",
+        params![price, symbol],
+    ).map_err(|e| format!("Database error: {}", e))
+}
+
+/// The most recent stored `(date, close)` for `symbol`, if any.
+pub fn latest_close(conn: &Connection, symbol: &str) -> Result<Option<(String, f64)>, String> {
+    conn.query_row(
+        "SELECT date, close FROM price_history WHERE symbol = ? ORDER BY date DESC LIMIT 1",
+        params![symbol],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional().map_err(|e| format!("Database error: {}", e))
+}
+
+/// Loads `(date, close)` pairs for `symbol` in ascending date order.
+pub fn load_price_history(conn: &Connection, symbol: &str, end_date: &str) -> Result<Vec<(String, f64)>, String> {
+    let mut stmt = conn.prepare("SELECT date, close FROM price_history WHERE symbol = ? AND date <= ? ORDER BY date")
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let rows = stmt.query_map(params![symbol, end_date], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+// Price commands
+#[tauri::command]
+pub fn update_prices(app: AppHandle, prices: Vec<PricePoint>) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    // Per symbol: newest date and close in the batch, and the newest date stored before it
+    let mut latest: Vec<(String, String, f64, Option<String>)> = Vec::new();
+    for point in &prices {
+        let symbol = normalize_symbol(&point.symbol);
+        let date = parse_trade_date(&point.date)?.format("%Y-%m-%d").to_string();
+        if point.close < 0.0 {
+            return Err(format!("Price for {} cannot be negative", symbol));
+        }
+
+        match latest.iter_mut().find(|(s, ..)| *s == symbol) {
+            Some(entry) if entry.1 <= date => {
+                entry.1 = date.clone();
+                entry.2 = point.close;
+            }
+            Some(_) => {}
+            None => {
+                let stored = latest_close(&tx, &symbol)?.map(|(stored_date, _)| stored_date);
+                latest.push((symbol.clone(), date.clone(), point.close, stored));
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO price_history (symbol, date, close) VALUES (?, ?, ?)
+             ON CONFLICT(symbol, date) DO UPDATE SET close = excluded.close",
+            params![symbol, date, point.close],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    // Only a quote at least as new as the stored history becomes the position's current price;
+    // backfilled closes just extend the history.
+    let mut updated = 0;
+    let mut triggered = Vec::new();
+    for (symbol, date, close, stored) in &latest {
+        if stored.as_ref().is_some_and(|stored| date < stored) {
+            continue;
+        }
+        updated += apply_current_price(&tx, symbol, *close)?;
+        triggered.extend(evaluate_alerts(&tx, symbol, *close)?);
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    drop(db_guard);
+
+    notify_triggered(&app, &triggered);
+    Ok(updated)
+}
+
+#[tauri::command]
+pub fn get_price_history(symbol: String, start_date: Option<String>, end_date: Option<String>) -> Result<Vec<PricePoint>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let symbol = normalize_symbol(&symbol);
+    let mut stmt = conn.prepare(
+        "SELECT symbol, date, close FROM price_history
+         WHERE symbol = ?1 AND (?2 IS NULL OR date >= ?2) AND (?3 IS NULL OR date <= ?3) ORDER BY date",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let points = stmt.query_map(params![symbol, start_date, end_date], |row| {
+        Ok(PricePoint {
+            symbol: row.get(0)?,
+            date: row.get(1)?,
+            close: row.get(2)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for point in points {
+        result.push(point.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
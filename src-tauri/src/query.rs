@@ -0,0 +1,297 @@
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, Row};
+use serde::{Deserialize, Serialize};
+
+/// Paging, sorting and filtering accepted by every list command. All fields are optional;
+/// with no limit the whole (filtered) table comes back in one page.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ListOptions {
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+    /// Opaque keyset cursor from a previous page's `next_cursor`; takes precedence over `offset`.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    #[serde(default)]
+    pub sort_dir: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub date_from: Option<String>,
+    #[serde(default)]
+    pub date_to: Option<String>,
+    /// Only rows carrying every one of these tags.
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// Only rows in this folder; `0` means rows that aren't in any folder.
+    #[serde(default)]
+    pub folder_id: Option<i64>,
+    /// Whether a folder filter also takes in its subfolders. Defaults to true;
+    /// has no effect on `folder_id: 0`.
+    #[serde(default)]
+    pub include_subfolders: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+/// Describes how a table can be listed. Sort entries map the public field name to the SQL
+/// expression ordered on; filters name the column each option applies to, if any.
+pub struct ListSpec {
+    pub table: &'static str,
+    /// Condition every listed row must meet, such as excluding trashed rows.
+    pub filter: Option<&'static str>,
+    pub columns: &'static str,
+    pub sortable: &'static [(&'static str, &'static str)],
+    pub default_sort: &'static str,
+    pub default_desc: bool,
+    pub category_column: Option<&'static str>,
+    pub status_column: Option<&'static str>,
+    pub date_column: Option<&'static str>,
+    /// Entity kind under which the table's rows are tagged, if they can be.
+    pub tag_kind: Option<&'static str>,
+    /// Column holding each row's folder and the folder table (with a `parent_id`), if rows can be filed.
+    pub folders: Option<(&'static str, &'static str)>,
+}
+
+fn to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => serde_json::json!(i),
+        Value::Real(f) => serde_json::json!(f),
+        Value::Text(s) => serde_json::Value::String(s),
+        Value::Blob(_) => serde_json::Value::Null,
+    }
+}
+
+fn from_json(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Number(n) if n.is_i64() => Value::Integer(n.as_i64().unwrap_or_default()),
+        serde_json::Value::Number(n) => Value::Real(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        _ => Value::Null,
+    }
+}
+
+/// Runs a paged listing. `map_row` sees the spec's columns starting at index 0.
+pub fn fetch_page<T, F>(
+    conn: &Connection,
+    spec: &ListSpec,
+    options: &ListOptions,
+    map_row: F,
+) -> Result<Page<T>, String>
+where
+    F: Fn(&Row) -> rusqlite::Result<T>,
+{
+    let (sort_name, sort_expr) = match &options.sort_by {
+        Some(name) => spec.sortable.iter()
+            .find(|(field, _)| field == name)
+            .ok_or_else(|| format!("Cannot sort by '{}'", name))?,
+        None => spec.sortable.iter()
+            .find(|(field, _)| *field == spec.default_sort)
+            .ok_or_else(|| format!("Cannot sort by '{}'", spec.default_sort))?,
+    };
+    let descending = match options.sort_dir.as_deref() {
+        Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => return Err(format!("Unknown sort direction '{}'", other)),
+        None if options.sort_by.is_none() => spec.default_desc,
+        None => false,
+    };
+
+    let mut conditions: Vec<String> = spec.filter.iter().map(|filter| filter.to_string()).collect();
+    let mut values: Vec<Value> = Vec::new();
+
+    // Date bounds compare calendar days, so `date_to` takes in the whole of its last day
+    let filters = [
+        (spec.category_column, &options.category, "category", "{} = ?"),
+        (spec.status_column, &options.status, "status", "{} = ?"),
+        (spec.date_column, &options.date_from, "date range", "substr({}, 1, 10) >= substr(?, 1, 10)"),
+        (spec.date_column, &options.date_to, "date range", "substr({}, 1, 10) <= substr(?, 1, 10)"),
+    ];
+    for (column, value, name, condition) in filters {
+        if let Some(value) = value {
+            let column = column.ok_or_else(|| format!("This list cannot be filtered by {}", name))?;
+            conditions.push(condition.replace("{}", column));
+            values.push(Value::Text(value.clone()));
+        }
+    }
+
+    for tag in options.tags.iter().flatten() {
+        let kind = spec.tag_kind.ok_or_else(|| "This list cannot be filtered by tag".to_string())?;
+        conditions.push(
+            "id IN (SELECT entity_tags.entity_id FROM entity_tags JOIN tags ON tags.id = entity_tags.tag_id
+                    WHERE entity_tags.entity_kind = ? AND tags.name = ?)".to_string(),
+        );
+        values.push(Value::Text(kind.to_string()));
+        values.push(Value::Text(tag.clone()));
+    }
+
+    if let Some(folder_id) = options.folder_id {
+        let (column, folder_table) = spec.folders.ok_or_else(|| "This list cannot be filtered by folder".to_string())?;
+        match (folder_id, options.include_subfolders.unwrap_or(true)) {
+            (0, _) => conditions.push(format!("{} IS NULL", column)),
+            (_, true) => {
+                conditions.push(format!(
+                    "{column} IN (WITH RECURSIVE subtree(id) AS (SELECT ? UNION SELECT {table}.id FROM {table} JOIN subtree ON {table}.parent_id = subtree.id) SELECT id FROM subtree)",
+                    column = column,
+                    table = folder_table,
+                ));
+                values.push(Value::Integer(folder_id));
+            }
+            (_, false) => {
+                conditions.push(format!("{} = ?", column));
+                values.push(Value::Integer(folder_id));
+            }
+        }
+    }
+
+    let filter_sql = if conditions.is_empty() { String::new() } else { format!(" WHERE {}", conditions.join(" AND ")) };
+    let total: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM {}{}", spec.table, filter_sql),
+        params_from_iter(values.iter()),
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    // Keyset paging resumes strictly after the (sort key, id) of the last row seen.
+    let mut page_conditions = conditions.clone();
+    let mut page_values = values.clone();
+    if let Some(cursor) = &options.cursor {
+        let parsed: (String, serde_json::Value, i64) = serde_json::from_str(cursor)
+            .map_err(|_| "Invalid cursor".to_string())?;
+        if parsed.0 != *sort_name {
+            return Err("Cursor was issued for a different sort order".to_string());
+        }
+        let operator = if descending { "<" } else { ">" };
+        page_conditions.push(format!("({}, id) {} (?, ?)", sort_expr, operator));
+        page_values.push(from_json(&parsed.1));
+        page_values.push(Value::Integer(parsed.2));
+    }
+
+    let direction = if descending { "DESC" } else { "ASC" };
+    let where_sql = if page_conditions.is_empty() { String::new() } else { format!(" WHERE {}", page_conditions.join(" AND ")) };
+    let offset = if options.cursor.is_some() { 0 } else { options.offset.unwrap_or(0) };
+    let sql = format!(
+        "SELECT {}, {} AS sort_key, id AS row_key FROM {}{} ORDER BY sort_key {}, row_key {} LIMIT ? OFFSET ?",
+        spec.columns, sort_expr, spec.table, where_sql, direction, direction
+    );
+    page_values.push(Value::Integer(options.limit.unwrap_or(-1)));
+    page_values.push(Value::Integer(offset));
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| format!("Database error: {}", e))?;
+    let key_index = stmt.column_count() - 2;
+
+    let rows = stmt.query_map(params_from_iter(page_values.iter()), |row| {
+        Ok((map_row(row)?, row.get::<_, Value>(key_index)?, row.get::<_, i64>(key_index + 1)?))
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut items = Vec::new();
+    let mut last_key = None;
+    for row in rows {
+        let (item, sort_key, id) = row.map_err(|e| format!("Database error: {}", e))?;
+        items.push(item);
+        last_key = Some((sort_key, id));
+    }
+
+    // A full page means there may be more rows after it.
+    let next_cursor = match (options.limit, last_key) {
+        (Some(limit), Some((sort_key, id))) if items.len() as i64 == limit => {
+            Some(serde_json::json!([sort_name, to_json(sort_key), id]).to_string())
+        }
+        _ => None,
+    };
+
+    Ok(Page { items, total, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: ListSpec = ListSpec {
+        table: "notes",
+        filter: None,
+        columns: "id, title",
+        sortable: &[("title", "title"), ("category", "COALESCE(category, '')"), ("created_at", "created_at")],
+        default_sort: "created_at",
+        default_desc: true,
+        category_column: Some("category"),
+        status_column: None,
+        date_column: Some("created_at"),
+        tag_kind: None,
+        folders: None,
+    };
+
+    fn test_db(rows: &[(&str, Option<&str>, &str)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE notes (id INTEGER PRIMARY KEY AUTOINCREMENT, title TEXT NOT NULL, category TEXT, created_at TEXT NOT NULL)", [])
+            .unwrap();
+        for (title, category, created_at) in rows {
+            conn.execute(
+                "INSERT INTO notes (title, category, created_at) VALUES (?, ?, ?)",
+                rusqlite::params![title, category, created_at],
+            ).unwrap();
+        }
+        conn
+    }
+
+    fn titles(conn: &Connection, options: &ListOptions) -> Vec<String> {
+        fetch_page(conn, &NOTES, options, |row| row.get::<_, String>(1)).unwrap().items
+    }
+
+    #[test]
+    fn date_range_includes_both_end_days() {
+        let conn = test_db(&[
+            ("before", None, "2024-04-30T23:59:59+00:00"),
+            ("first", None, "2024-05-01T00:00:00+00:00"),
+            ("last", None, "2024-05-31T09:00:00+00:00"),
+            ("after", None, "2024-06-01T00:00:00+00:00"),
+        ]);
+        let options = ListOptions {
+            date_from: Some("2024-05-01".to_string()),
+            date_to: Some("2024-05-31".to_string()),
+            sort_by: Some("title".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(&conn, &options), ["first", "last"]);
+    }
+
+    #[test]
+    fn keyset_pages_reach_rows_with_null_sort_keys() {
+        let conn = test_db(&[
+            ("a", Some("work"), "2024-05-01"),
+            ("b", None, "2024-05-02"),
+            ("c", Some("home"), "2024-05-03"),
+            ("d", None, "2024-05-04"),
+            ("e", Some("work"), "2024-05-05"),
+        ]);
+        for sort_dir in ["asc", "desc"] {
+            let mut options = ListOptions {
+                sort_by: Some("category".to_string()),
+                sort_dir: Some(sort_dir.to_string()),
+                limit: Some(2),
+                ..Default::default()
+            };
+            let mut seen = Vec::new();
+            loop {
+                let page = fetch_page(&conn, &NOTES, &options, |row| row.get::<_, String>(1)).unwrap();
+                seen.extend(page.items);
+                match page.next_cursor {
+                    Some(cursor) => options.cursor = Some(cursor),
+                    None => break,
+                }
+            }
+            let expected = if sort_dir == "asc" { ["b", "d", "c", "a", "e"] } else { ["e", "a", "c", "d", "b"] };
+            assert_eq!(seen, expected);
+        }
+    }
+}
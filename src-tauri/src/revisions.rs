@@ -0,0 +1,243 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use crate::DB;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteRevisionSummary {
+    id: i32,
+    note_id: i32,
+    title: String,
+    created_at: String,
+    content_length: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteRevision {
+    id: i32,
+    note_id: i32,
+    title: String,
+    content: String,
+    category: String,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// One of `equal`, `insert` or `delete`.
+    tag: String,
+    old_line: Option<usize>,
+    new_line: Option<usize>,
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteRevisionDiff {
+    from_revision: i32,
+    to_revision: i32,
+    insertions: usize,
+    deletions: usize,
+    lines: Vec<DiffLine>,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS note_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT,
+            category TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_note_revisions_note ON note_revisions(note_id, id)", [])?;
+
+    Ok(())
+}
+
+/// Records the note's current state unless it matches the newest revision, then prunes.
+/// Calling this before an update also captures notes that predate revision tracking.
+pub fn snapshot_note(conn: &Connection, note_id: i32) -> Result<(), String> {
+    // Encrypted notes keep no plaintext history
+    if crate::vault::is_encrypted(conn, note_id) {
+        return Ok(());
+    }
+
+    let current: Option<(String, String, String)> = conn.query_row(
+        "SELECT title, COALESCE(content, ''), COALESCE(category, '') FROM notes WHERE id = ?",
+        params![note_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).ok();
+    let (title, content, category) = match current {
+        Some(current) => current,
+        None => return Ok(()),
+    };
+
+    let latest: Option<(String, String, String)> = conn.query_row(
+        "SELECT title, COALESCE(content, ''), COALESCE(category, '') FROM note_revisions WHERE note_id = ? ORDER BY id DESC LIMIT 1",
+        params![note_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).ok();
+    if latest.as_ref() == Some(&(title.clone(), content.clone(), category.clone())) {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO note_revisions (note_id, title, content, category, created_at) VALUES (?, ?, ?, ?, ?)",
+        params![note_id, title, content, category, now],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    prune_revisions(conn, note_id)
+}
+
+/// Applies the retention policy from settings. The newest revision always survives.
+fn prune_revisions(conn: &Connection, note_id: i32) -> Result<(), String> {
+    let (keep, max_age_days): (i64, i64) = conn.query_row(
+        "SELECT COALESCE(note_revision_limit, 50), COALESCE(note_revision_max_age_days, 0) FROM settings LIMIT 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).unwrap_or((50, 0));
+
+    if keep > 0 {
+        conn.execute(
+            "DELETE FROM note_revisions WHERE note_id = ?1 AND id NOT IN (
+                SELECT id FROM note_revisions WHERE note_id = ?1 ORDER BY id DESC LIMIT ?2
+            )",
+            params![note_id, keep],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    if max_age_days > 0 {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::days(max_age_days)).to_rfc3339();
+        conn.execute(
+            "DELETE FROM note_revisions WHERE note_id = ?1 AND created_at < ?2
+             AND id != (SELECT MAX(id) FROM note_revisions WHERE note_id = ?1)",
+            params![note_id, cutoff],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    Ok(())
+}
+
+fn load_revision(conn: &Connection, id: i32) -> Result<NoteRevision, String> {
+    conn.query_row(
+        "SELECT id, note_id, title, COALESCE(content, ''), COALESCE(category, ''), created_at FROM note_revisions WHERE id = ?",
+        params![id],
+        |row| {
+            Ok(NoteRevision {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                category: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        },
+    ).map_err(|_| format!("Note revision {} not found", id))
+}
+
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLine {
+            tag: match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Insert => "insert",
+                ChangeTag::Delete => "delete",
+            }.to_string(),
+            old_line: change.old_index().map(|i| i + 1),
+            new_line: change.new_index().map(|i| i + 1),
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect()
+}
+
+// Note revision commands
+#[tauri::command]
+pub fn list_note_revisions(note_id: i32) -> Result<Vec<NoteRevisionSummary>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, note_id, title, created_at, LENGTH(COALESCE(content, '')) FROM note_revisions WHERE note_id = ? ORDER BY id DESC",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let revisions = stmt.query_map(params![note_id], |row| {
+        Ok(NoteRevisionSummary {
+            id: row.get(0)?,
+            note_id: row.get(1)?,
+            title: row.get(2)?,
+            created_at: row.get(3)?,
+            content_length: row.get(4)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for revision in revisions {
+        result.push(revision.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn get_note_revision(id: i32) -> Result<NoteRevision, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    load_revision(conn, id)
+}
+
+#[tauri::command]
+pub fn diff_note_revisions(from_id: i32, to_id: i32) -> Result<NoteRevisionDiff, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let from = load_revision(conn, from_id)?;
+    let to = load_revision(conn, to_id)?;
+    if from.note_id != to.note_id {
+        return Err("Revisions belong to different notes".to_string());
+    }
+
+    let lines = diff_lines(&from.content, &to.content);
+    Ok(NoteRevisionDiff {
+        from_revision: from_id,
+        to_revision: to_id,
+        insertions: lines.iter().filter(|line| line.tag == "insert").count(),
+        deletions: lines.iter().filter(|line| line.tag == "delete").count(),
+        lines,
+    })
+}
+
+#[tauri::command]
+pub fn restore_note_revision(revision_id: i32) -> Result<i32, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let revision = load_revision(conn, revision_id)?;
+    if crate::vault::is_encrypted(conn, revision.note_id) {
+        return Err("Decrypt the note before restoring a revision".to_string());
+    }
+    let now = chrono::Utc::now().to_rfc3339();
+
+    snapshot_note(conn, revision.note_id)?;
+    let updated = conn.execute(
+        "UPDATE notes SET title = ?, content = ?, category = ?, updated_at = ?, deleted_at = NULL WHERE id = ?",
+        params![revision.title, revision.content, revision.category, now, revision.note_id],
+    ).map_err(|e| format!("Database error: {}", e))?;
+    if updated == 0 {
+        // The note itself was deleted; bring it back under its old id
+        conn.execute(
+            "INSERT INTO notes (id, title, content, category, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![revision.note_id, revision.title, revision.content, revision.category, revision.created_at, now],
+        ).map_err(|e| format!("Database error: {}", e))?;
+    }
+    snapshot_note(conn, revision.note_id)?;
+    crate::links::sync_note_links(conn, revision.note_id, &revision.content)?;
+
+    Ok(revision.note_id)
+}
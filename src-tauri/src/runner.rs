@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::DB;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+/// Output kept per stream in the run history; everything is still streamed live.
+const MAX_STORED_OUTPUT: usize = 256 * 1024;
+/// Templates carrying this tag only run once the caller confirms.
+const DANGEROUS_TAG: &str = "dangerous";
+/// The only variables a run inherits from the app's environment.
+const INHERITED_ENV: &[&str] = &["PATH", "HOME", "USER", "LANG", "LC_ALL", "TZ"];
+/// Largest file a run may write, in the 512-byte blocks `ulimit -f` counts (1 GiB).
+const MAX_FILE_BLOCKS: u64 = 2 * 1024 * 1024;
+/// Address space a run may map, in KiB (4 GiB).
+const MAX_MEMORY_KB: u64 = 4 * 1024 * 1024;
+/// How long to keep reading output after the run's process group is gone.
+const PIPE_GRACE: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    // Cancellation flags for runs that are still going, keyed by run id.
+    static ref RUNNING: Mutex<HashMap<i64, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandRun {
+    id: i64,
+    template_id: i32,
+    command: String,
+    cwd: Option<String>,
+    /// One of `running`, `succeeded`, `failed`, `timed_out`, `cancelled` or `interrupted`.
+    status: String,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+    started_at: String,
+    finished_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunOutput {
+    run_id: i64,
+    /// `stdout` or `stderr`.
+    stream: &'static str,
+    line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunFinished {
+    run_id: i64,
+    status: String,
+    exit_code: Option<i32>,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS command_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            template_id INTEGER NOT NULL,
+            command TEXT NOT NULL,
+            cwd TEXT,
+            status TEXT NOT NULL,
+            exit_code INTEGER,
+            stdout TEXT NOT NULL DEFAULT '',
+            stderr TEXT NOT NULL DEFAULT '',
+            started_at TEXT NOT NULL,
+            finished_at TEXT
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_command_runs_template ON command_runs(template_id, started_at)", [])?;
+
+    // Runs cut short by the app closing never recorded an outcome
+    conn.execute("UPDATE command_runs SET status = 'interrupted' WHERE status = 'running'", [])?;
+
+    Ok(())
+}
+
+fn is_dangerous(conn: &Connection, template_id: i32) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT COUNT(*) > 0 FROM entity_tags JOIN tags ON tags.id = entity_tags.tag_id
+         WHERE entity_tags.entity_kind = 'command' AND entity_tags.entity_id = ? AND tags.name = ?",
+        params![template_id, DANGEROUS_TAG],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))
+}
+
+/// Working directory for runs that don't name one, so they never start in the app's own.
+fn default_run_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("command-runs");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create run directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Builds the sandboxed shell: its own process group, a cleared environment apart from
+/// `INHERITED_ENV`, and CPU, file size and memory limits applied before the command starts.
+fn shell(command: &str, cwd: &Path, timeout: Duration) -> Command {
+    // dash takes one limit per `ulimit`
+    let mut limits = format!("ulimit -t {} && ulimit -f {}", timeout.as_secs(), MAX_FILE_BLOCKS);
+    // Other systems' shells reject or ignore an address space limit
+    if cfg!(target_os = "linux") {
+        limits.push_str(&format!(" && ulimit -v {}", MAX_MEMORY_KB));
+    }
+
+    let mut shell = Command::new("/bin/sh");
+    // The command travels as `$1` so the limits wrapper never has to quote it
+    shell.args(["-c", &format!("{} || exit 126; exec /bin/sh -c \"$1\"", limits), "sh", command]);
+    shell.env_clear().current_dir(cwd);
+    for name in INHERITED_ENV {
+        if let Some(value) = std::env::var_os(name) {
+            shell.env(name, value);
+        }
+    }
+    // Own process group, so killing the run also takes down anything it started
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+    shell
+}
+
+/// Kills everything left in the run's process group, including backgrounded children.
+fn kill_group(pid: u32) {
+    let _ = Command::new("kill")
+        .args(["-s", "KILL", "--", &format!("-{}", pid)])
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// The child's exit status once it has exited. On Unix the process group is killed before the
+/// child is reaped: until then its pid, and so the group id, can't go to another process.
+fn reap_if_exited(child: &mut Child) -> std::io::Result<Option<ExitStatus>> {
+    #[cfg(unix)]
+    {
+        // SAFETY: `info` is a plain C struct that `waitid` fills in; WNOWAIT leaves the child unreaped
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let result = unsafe {
+            libc::waitid(libc::P_PID, child.id() as libc::id_t, &mut info, libc::WEXITED | libc::WNOHANG | libc::WNOWAIT)
+        };
+        if result == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if unsafe { info.si_pid() } == 0 {
+            return Ok(None);
+        }
+        // Anything the shell left running in the background goes with it
+        kill_group(child.id());
+    }
+    child.try_wait()
+}
+
+fn kill(child: &mut Child) {
+    kill_group(child.id());
+    let _ = child.kill();
+}
+
+/// Output read from one pipe so far.
+struct Capture {
+    kept: Arc<Mutex<String>>,
+    /// Disconnects once the pipe closes.
+    finished: mpsc::Receiver<()>,
+}
+
+/// Streams one pipe line by line as `command-run-output` events, keeping a capped copy.
+fn forward_output<R: Read + Send + 'static>(app: AppHandle, run_id: i64, stream: &'static str, pipe: R) -> Capture {
+    let kept = Arc::new(Mutex::new(String::new()));
+    let (done, finished) = mpsc::channel::<()>();
+    let output = kept.clone();
+    std::thread::spawn(move || {
+        let _done = done;
+        let mut reader = BufReader::new(pipe);
+        let mut buffer = Vec::new();
+        while let Ok(read) = reader.read_until(b'\n', &mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buffer).trim_end_matches(['\r', '\n']).to_string();
+            buffer.clear();
+
+            {
+                let mut kept = output.lock().unwrap();
+                if kept.len() < MAX_STORED_OUTPUT {
+                    kept.push_str(&line);
+                    kept.push('\n');
+                }
+            }
+            if let Err(e) = app.emit("command-run-output", RunOutput { run_id, stream, line }) {
+                eprintln!("Failed to emit command output: {}", e);
+            }
+        }
+    });
+    Capture { kept, finished }
+}
+
+/// Waits up to `PIPE_GRACE` for the pipes to close, then takes whatever output was read. A
+/// process that escaped the group can hold a pipe open forever; its reader is left behind.
+fn collect_output(captures: [Option<Capture>; 2]) -> [String; 2] {
+    let deadline = Instant::now() + PIPE_GRACE;
+    captures.map(|capture| match capture {
+        Some(capture) => {
+            let _ = capture.finished.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+            let output = capture.kept.lock().unwrap().clone();
+            output
+        }
+        None => String::new(),
+    })
+}
+
+fn supervise(app: AppHandle, run_id: i64, mut child: Child, timeout: Duration, cancelled: Arc<AtomicBool>) {
+    let stdout = child.stdout.take().map(|pipe| forward_output(app.clone(), run_id, "stdout", pipe));
+    let stderr = child.stderr.take().map(|pipe| forward_output(app.clone(), run_id, "stderr", pipe));
+
+    let started = Instant::now();
+    let (status, exit_code) = loop {
+        match reap_if_exited(&mut child) {
+            Ok(Some(exit)) => {
+                let status = if exit.success() { "succeeded" } else { "failed" };
+                break (status, exit.code());
+            }
+            Ok(None) if cancelled.load(Ordering::SeqCst) => {
+                kill(&mut child);
+                break ("cancelled", child.wait().ok().and_then(|exit| exit.code()));
+            }
+            Ok(None) if started.elapsed() >= timeout => {
+                kill(&mut child);
+                break ("timed_out", child.wait().ok().and_then(|exit| exit.code()));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => {
+                eprintln!("Failed to wait for command run {}: {}", run_id, e);
+                kill(&mut child);
+                break ("failed", None);
+            }
+        }
+    };
+
+    let [stdout, stderr] = collect_output([stdout, stderr]);
+    RUNNING.lock().unwrap().remove(&run_id);
+
+    {
+        let db_guard = DB.lock().unwrap();
+        if let Some(conn) = db_guard.as_ref() {
+            let recorded = conn.execute(
+                "UPDATE command_runs SET status = ?, exit_code = ?, stdout = ?, stderr = ?, finished_at = ? WHERE id = ?",
+                params![status, exit_code, stdout, stderr, chrono::Utc::now().to_rfc3339(), run_id],
+            );
+            if let Err(e) = recorded {
+                eprintln!("Failed to record command run {}: {}", run_id, e);
+            }
+        }
+    }
+
+    let finished = RunFinished { run_id, status: status.to_string(), exit_code };
+    if let Err(e) = app.emit("command-run-finished", finished) {
+        eprintln!("Failed to emit command run result: {}", e);
+    }
+}
+
+// Command runner commands
+/// Starts the rendered template in the background and returns the run id; output arrives as
+/// `command-run-output` events and the outcome as `command-run-finished`.
+#[tauri::command]
+pub fn run_command_template(
+    app: AppHandle,
+    id: i32,
+    values: HashMap<String, String>,
+    cwd: Option<String>,
+    timeout_secs: Option<u64>,
+    confirmed: Option<bool>,
+) -> Result<i64, String> {
+    // Templates are rendered with POSIX shell quoting, which `cmd` doesn't understand
+    if cfg!(windows) {
+        return Err("Running command templates is only supported on macOS and Linux".to_string());
+    }
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let command = crate::templates::render(&crate::templates::template_command(conn, id)?, &values)?;
+    if is_dangerous(conn, id)? && !confirmed.unwrap_or(false) {
+        return Err(format!("Command template {} is tagged {}; confirm before running it", id, DANGEROUS_TAG));
+    }
+    let cwd = cwd.filter(|dir| !dir.trim().is_empty());
+    if let Some(dir) = &cwd {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("Working directory '{}' does not exist", dir));
+        }
+    }
+
+    let run_dir = match &cwd {
+        Some(dir) => PathBuf::from(dir),
+        None => default_run_dir(&app)?,
+    };
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1));
+
+    let mut process = shell(&command, &run_dir, timeout);
+    process.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = process.spawn()
+        .map_err(|e| format!("Failed to start command: {}", e))?;
+
+    let inserted = conn.execute(
+        "INSERT INTO command_runs (template_id, command, cwd, status, started_at) VALUES (?, ?, ?, 'running', ?)",
+        params![id, command, cwd, chrono::Utc::now().to_rfc3339()],
+    );
+    if let Err(e) = inserted {
+        kill(&mut child);
+        return Err(format!("Database error: {}", e));
+    }
+    let run_id = conn.last_insert_rowid();
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    RUNNING.lock().unwrap().insert(run_id, cancelled.clone());
+    std::thread::spawn(move || supervise(app, run_id, child, timeout, cancelled));
+
+    if let Err(e) = crate::usage::record_use(conn, id) {
+        eprintln!("Failed to record usage of command template {}: {}", id, e);
+    }
+
+    Ok(run_id)
+}
+
+#[tauri::command]
+pub fn cancel_command_run(run_id: i64) -> Result<(), String> {
+    let running = RUNNING.lock().unwrap();
+    let cancelled = running.get(&run_id)
+        .ok_or_else(|| format!("Command run {} is not running", run_id))?;
+    cancelled.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_command_runs(template_id: Option<i32>, limit: Option<i64>) -> Result<Vec<CommandRun>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, template_id, command, cwd, status, exit_code, stdout, stderr, started_at, finished_at
+         FROM command_runs WHERE ?1 IS NULL OR template_id = ?1
+         ORDER BY started_at DESC, id DESC LIMIT ?2",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let runs = stmt.query_map(params![template_id, limit.unwrap_or(50)], |row| {
+        Ok(CommandRun {
+            id: row.get(0)?,
+            template_id: row.get(1)?,
+            command: row.get(2)?,
+            cwd: row.get(3)?,
+            status: row.get(4)?,
+            exit_code: row.get(5)?,
+            stdout: row.get(6)?,
+            stderr: row.get(7)?,
+            started_at: row.get(8)?,
+            finished_at: row.get(9)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for run in runs {
+        result.push(run.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,392 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::{snippet_folders, tags, DB};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteSearchResult {
+    id: i32,
+    title: String,
+    category: String,
+    updated_at: String,
+    rank: f64,
+    title_highlight: String,
+    snippet: String,
+}
+
+// FTS5 wraps matches in these control characters; they are swapped for <mark> after escaping.
+const MATCH_START: &str = "\u{2}";
+const MATCH_END: &str = "\u{3}";
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'notes_fts'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+            title,
+            content,
+            content='notes',
+            content_rowid='id',
+            tokenize='porter unicode61'
+        )",
+        [],
+    )?;
+
+    // Keep the index in step with every write to notes
+    conn.execute_batch(
+        "CREATE TRIGGER IF NOT EXISTS notes_fts_insert AFTER INSERT ON notes BEGIN
+            INSERT INTO notes_fts(rowid, title, content) VALUES (new.id, new.title, new.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS notes_fts_delete AFTER DELETE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, content) VALUES ('delete', old.id, old.title, old.content);
+        END;
+        CREATE TRIGGER IF NOT EXISTS notes_fts_update AFTER UPDATE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, title, content) VALUES ('delete', old.id, old.title, old.content);
+            INSERT INTO notes_fts(rowid, title, content) VALUES (new.id, new.title, new.content);
+        END;",
+    )?;
+
+    // Notes written before the index existed need a one-time backfill
+    if !exists {
+        conn.execute("INSERT INTO notes_fts(notes_fts) VALUES ('rebuild')", [])?;
+    }
+
+    Ok(())
+}
+
+/// Turns user input into an FTS5 query. Quoted text becomes a phrase, a trailing `*`
+/// makes a prefix match, and everything else is quoted so FTS5 syntax can't leak in.
+pub fn build_match_query(input: &str) -> Option<String> {
+    let mut terms = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+        if let Some(after_quote) = rest.strip_prefix('"') {
+            let end = after_quote.find('"').unwrap_or(after_quote.len());
+            let phrase: String = after_quote[..end].split_whitespace().map(clean_term).collect::<Vec<_>>().join(" ");
+            if !phrase.trim().is_empty() {
+                terms.push(format!("\"{}\"", phrase.trim()));
+            }
+            rest = after_quote.get(end + 1..).unwrap_or("");
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let word = &rest[..end];
+            let prefix = word.ends_with('*');
+            let cleaned = clean_term(word);
+            if !cleaned.is_empty() {
+                terms.push(format!("\"{}\"{}", cleaned, if prefix { "*" } else { "" }));
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn clean_term(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '\'' | '.')).collect()
+}
+
+/// Escapes FTS5 highlight output for HTML and swaps the match markers for `<mark>` tags.
+pub fn highlight_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+// Search commands
+#[tauri::command]
+pub fn search_notes(query: String, category: Option<String>, limit: Option<i64>, offset: Option<i64>) -> Result<Vec<NoteSearchResult>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let match_query = match build_match_query(&query) {
+        Some(match_query) => match_query,
+        None => return Ok(Vec::new()),
+    };
+
+    // Title hits weigh more than body hits
+    let mut stmt = conn.prepare(
+        "SELECT notes.id, notes.title, notes.category, notes.updated_at,
+                bm25(notes_fts, 10.0, 1.0) AS rank,
+                highlight(notes_fts, 0, ?2, ?3),
+                snippet(notes_fts, 1, ?2, ?3, '…', 24)
+         FROM notes_fts JOIN notes ON notes.id = notes_fts.rowid
+         WHERE notes_fts MATCH ?1 AND notes.deleted_at IS NULL AND (?4 IS NULL OR notes.category = ?4)
+         ORDER BY rank
+         LIMIT ?5 OFFSET ?6",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let results = stmt.query_map(
+        params![match_query, MATCH_START, MATCH_END, category, limit.unwrap_or(50), offset.unwrap_or(0)],
+        |row| {
+            Ok(NoteSearchResult {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                category: row.get(2)?,
+                updated_at: row.get(3)?,
+                rank: row.get(4)?,
+                title_highlight: highlight_html(&row.get::<_, String>(5)?),
+                snippet: highlight_html(&row.get::<_, Option<String>>(6)?.unwrap_or_default()),
+            })
+        },
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for note in results {
+        result.push(note.map_err(|e| format!("Search error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalSearchResult {
+    /// One of `note`, `snippet`, `command`, `invoice` or `portfolio`.
+    kind: String,
+    id: i32,
+    title: String,
+    subtitle: String,
+    score: f64,
+}
+
+// How many candidates each module contributes before the merged list is ranked
+const CANDIDATES_PER_KIND: i64 = 50;
+/// Boost per unit of log frecency for command templates.
+const FRECENCY_WEIGHT: f64 = 10.0;
+
+const SNIPPET_SELECT: &str = concat!("SELECT id, title, language, description, code, ", tags::tag_names_sql!("snippet", "code_snippets.id"), " FROM code_snippets");
+const SNIPPET_FIELDS: &[&str] = &["title", "description", "code", tags::tag_names_sql!("snippet", "code_snippets.id")];
+const SNIPPET_ORDER: &str = "updated_at DESC";
+
+fn search_terms(query: &str) -> Vec<String> {
+    query.split_whitespace()
+        .map(|term| term.trim_matches(|c: char| c == '"' || c == '*').to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Ranks a hit by where the terms appear: the title counts for far more than other fields.
+fn score_fields(query: &str, terms: &[String], title: &str, others: &[&str]) -> f64 {
+    let title = title.to_lowercase();
+    let others: Vec<String> = others.iter().map(|field| field.to_lowercase()).collect();
+    let mut score = 0.0;
+
+    if title == query.trim().to_lowercase() {
+        score += 100.0;
+    }
+    for term in terms {
+        if title.starts_with(term.as_str()) {
+            score += 30.0;
+        } else if title.contains(term.as_str()) {
+            score += 20.0;
+        }
+        if others.iter().any(|field| field.contains(term.as_str())) {
+            score += 5.0;
+        }
+    }
+
+    score
+}
+
+/// A row matched by LIKE, before scoring. `others` holds the remaining searchable columns.
+struct Candidate {
+    id: i32,
+    title: String,
+    subtitle: String,
+    others: Vec<String>,
+    /// The stored frecency key, when the query selects a `frecency` column.
+    frecency: Option<f64>,
+}
+
+/// Runs `sql` with one LIKE pattern per term bound in order.
+fn like_candidates(conn: &Connection, sql: &str, terms: &[String]) -> Result<Vec<Candidate>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("Database error: {}", e))?;
+    let column_count = stmt.column_count();
+    let frecency_column = stmt.column_index("frecency").ok();
+
+    let patterns: Vec<String> = terms.iter().map(|term| like_pattern(term)).collect();
+    let rows = stmt.query_map(rusqlite::params_from_iter(patterns.iter()), |row| {
+        let mut others = Vec::new();
+        for index in (3..column_count).filter(|index| Some(*index) != frecency_column) {
+            others.push(row.get::<_, Option<String>>(index)?.unwrap_or_default());
+        }
+        Ok(Candidate {
+            id: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            subtitle: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            others,
+            frecency: frecency_column.map(|index| row.get(index)).transpose()?.flatten(),
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+/// Builds `SELECT ... WHERE deleted_at IS NULL AND (f1 LIKE ? OR f2 LIKE ?) AND ...` requiring every term in some field,
+/// plus `scope` when given. Rows with more terms in their title (the first field) come first, then `order` breaks
+/// ties, so the candidate limit keeps the likeliest hits for scoring.
+fn like_query(select: &str, fields: &[&str], term_count: usize, scope: Option<&str>, order: &str) -> String {
+    let clause = fields.iter()
+        .map(|field| format!("{} LIKE ?{{n}} ESCAPE '\\'", field))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+    let mut conditions: Vec<String> = (1..=term_count)
+        .map(|n| format!("({})", clause.replace("{n}", &n.to_string())))
+        .collect();
+    conditions.extend(scope.map(str::to_string));
+    let title_hits = (1..=term_count)
+        .map(|n| format!("({} LIKE ?{} ESCAPE '\\')", fields[0], n))
+        .collect::<Vec<_>>()
+        .join(" + ");
+    format!(
+        "{} WHERE deleted_at IS NULL AND {} ORDER BY {} DESC, {} LIMIT {}",
+        select, conditions.join(" AND "), title_hits, order, CANDIDATES_PER_KIND
+    )
+}
+
+pub fn run_global_search(conn: &Connection, query: &str, limit: usize) -> Result<Vec<GlobalSearchResult>, String> {
+    let terms = search_terms(query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut results = Vec::new();
+
+    // Notes go through the full-text index
+    if let Some(match_query) = build_match_query(query) {
+        let mut stmt = conn.prepare(
+            "SELECT notes.id, notes.title, notes.category, notes.content FROM notes_fts JOIN notes ON notes.id = notes_fts.rowid
+             WHERE notes_fts MATCH ? AND notes.deleted_at IS NULL ORDER BY bm25(notes_fts, 10.0, 1.0) LIMIT ?",
+        ).map_err(|e| format!("Database error: {}", e))?;
+        let notes = stmt.query_map(params![match_query, CANDIDATES_PER_KIND], |row| {
+            Ok((
+                row.get::<_, i32>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+            ))
+        }).map_err(|e| format!("Database error: {}", e))?;
+        for note in notes {
+            let (id, title, category, content) = note.map_err(|e| format!("Search error: {}", e))?;
+            // The index already matched, so even body-only hits keep a floor score
+            let score = score_fields(query, &terms, &title, &[&content]).max(5.0);
+            results.push(GlobalSearchResult { kind: "note".to_string(), id, title, subtitle: category, score });
+        }
+    }
+
+    let sources: [(&str, &str, &[&str], &str); 4] = [
+        ("snippet", SNIPPET_SELECT, SNIPPET_FIELDS, SNIPPET_ORDER),
+        (
+            "command",
+            concat!("SELECT id, title, command, description, category, ", tags::tag_names_sql!("command", "command_templates.id"), ", frecency FROM command_templates"),
+            &["title", "command", "description", tags::tag_names_sql!("command", "command_templates.id")],
+            "COALESCE(frecency, -1e300) DESC, id DESC",
+        ),
+        (
+            "invoice",
+            "SELECT id, invoice_number, client_name, client_email FROM invoices",
+            &["invoice_number", "client_name", "client_email"],
+            "id DESC",
+        ),
+        (
+            "portfolio",
+            "SELECT id, symbol, notes FROM portfolio",
+            &["symbol", "notes"],
+            "id DESC",
+        ),
+    ];
+
+    for (kind, select, fields, order) in sources {
+        let sql = like_query(select, fields, terms.len(), None, order);
+        for candidate in like_candidates(conn, &sql, &terms)? {
+            let mut fields: Vec<&str> = candidate.others.iter().map(String::as_str).collect();
+            fields.push(&candidate.subtitle);
+            let mut score = score_fields(query, &terms, &candidate.title, &fields);
+            if kind == "command" {
+                // Templates in regular use float above equally good matches
+                score += FRECENCY_WEIGHT * crate::usage::current_score(candidate.frecency).ln_1p();
+            }
+            results.push(GlobalSearchResult {
+                kind: kind.to_string(),
+                id: candidate.id,
+                title: candidate.title,
+                subtitle: candidate.subtitle,
+                score,
+            });
+        }
+    }
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(limit);
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn global_search(query: String, limit: Option<usize>) -> Result<Vec<GlobalSearchResult>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    run_global_search(conn, &query, limit.unwrap_or(25))
+}
+
+/// Searches snippets alone, optionally only within a folder and its subfolders, or only at the top level for `0`.
+#[tauri::command]
+pub fn search_snippets(query: String, folder_id: Option<i32>, limit: Option<usize>) -> Result<Vec<GlobalSearchResult>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let terms = search_terms(&query);
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let scope = match folder_id {
+        Some(0) => Some("folder_id IS NULL".to_string()),
+        Some(folder_id) => {
+            snippet_folders::require_folder(conn, folder_id)?;
+            let ids: Vec<String> = snippet_folders::subtree_ids(conn, folder_id)?.iter().map(i32::to_string).collect();
+            Some(format!("folder_id IN ({})", ids.join(", ")))
+        }
+        None => None,
+    };
+
+    let sql = like_query(SNIPPET_SELECT, SNIPPET_FIELDS, terms.len(), scope.as_deref(), SNIPPET_ORDER);
+    let mut results: Vec<GlobalSearchResult> = like_candidates(conn, &sql, &terms)?.into_iter().map(|candidate| {
+        let mut fields: Vec<&str> = candidate.others.iter().map(String::as_str).collect();
+        fields.push(&candidate.subtitle);
+        GlobalSearchResult {
+            kind: "snippet".to_string(),
+            id: candidate.id,
+            score: score_fields(&query, &terms, &candidate.title, &fields),
+            title: candidate.title,
+            subtitle: candidate.subtitle,
+        }
+    }).collect();
+
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(limit.unwrap_or(25));
+    Ok(results)
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::{add_column_if_missing, DB};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetFolder {
+    id: Option<i32>,
+    name: String,
+    /// `None` for top-level folders.
+    parent_id: Option<i32>,
+    /// Folder names from the top level down, joined with `/`.
+    #[serde(default)]
+    path: String,
+    /// Live snippets directly inside, not counting subfolders.
+    #[serde(default)]
+    snippet_count: i64,
+    #[serde(default)]
+    created_at: String,
+    #[serde(default)]
+    updated_at: String,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snippet_folders (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            parent_id INTEGER REFERENCES snippet_folders(id),
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_snippet_folders_parent ON snippet_folders(parent_id)", [])?;
+
+    add_column_if_missing(conn, "code_snippets", "folder_id", "INTEGER REFERENCES snippet_folders(id)")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_code_snippets_folder ON code_snippets(folder_id)", [])?;
+
+    Ok(())
+}
+
+/// Every folder as id -> (name, parent id).
+fn load_tree(conn: &Connection) -> Result<HashMap<i32, (String, Option<i32>)>, String> {
+    let mut stmt = conn.prepare("SELECT id, name, parent_id FROM snippet_folders")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    rows.collect::<Result<HashMap<_, _>, _>>().map_err(|e| format!("Database error: {}", e))
+}
+
+/// Names from the top level down to `id`, stopping short of `root`.
+fn path_names(tree: &HashMap<i32, (String, Option<i32>)>, id: i32, root: Option<i32>) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut current = Some(id);
+    // The depth guard only matters if the table was edited into a cycle by hand
+    while let Some(folder) = current.filter(|folder| Some(*folder) != root && names.len() <= tree.len()) {
+        let Some((name, parent)) = tree.get(&folder) else { break };
+        names.push(name.clone());
+        current = *parent;
+    }
+    names.reverse();
+    names
+}
+
+/// `id` and every folder below it.
+pub fn subtree_ids(conn: &Connection, id: i32) -> Result<Vec<i32>, String> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE subtree(id) AS (
+            SELECT ?1 UNION SELECT snippet_folders.id FROM snippet_folders JOIN subtree ON snippet_folders.parent_id = subtree.id
+        ) SELECT id FROM subtree",
+    ).map_err(|e| format!("Database error: {}", e))?;
+    let rows = stmt.query_map(params![id], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    rows.collect::<Result<Vec<i32>, _>>().map_err(|e| format!("Database error: {}", e))
+}
+
+/// Path of every folder under `root` (or every folder, for `None`) relative to it.
+pub fn relative_paths(conn: &Connection, root: Option<i32>) -> Result<HashMap<i32, Vec<String>>, String> {
+    let tree = load_tree(conn)?;
+    let ids: Vec<i32> = match root {
+        Some(root) => subtree_ids(conn, root)?,
+        None => tree.keys().copied().collect(),
+    };
+
+    Ok(ids.into_iter().map(|id| (id, path_names(&tree, id, root))).collect())
+}
+
+pub fn require_folder(conn: &Connection, id: i32) -> Result<(), String> {
+    conn.query_row("SELECT id FROM snippet_folders WHERE id = ?", params![id], |row| row.get::<_, i32>(0))
+        .map(|_| ())
+        .map_err(|_| format!("Snippet folder {} not found", id))
+}
+
+fn child(conn: &Connection, parent: Option<i32>, name: &str) -> Result<Option<i32>, String> {
+    conn.query_row(
+        "SELECT id FROM snippet_folders WHERE parent_id IS ? AND name = ? COLLATE NOCASE ORDER BY id LIMIT 1",
+        params![parent, name],
+        |row| row.get(0),
+    ).optional().map_err(|e| format!("Database error: {}", e))
+}
+
+fn validate_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("'{}' is not a valid folder name", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Follows `names` down from `root` without creating anything. The outer `None` means the
+/// path doesn't exist; `Some(None)` is the top level.
+pub fn lookup_path(conn: &Connection, root: Option<i32>, names: &[&str]) -> Result<Option<Option<i32>>, String> {
+    let mut current = root;
+    for name in names {
+        match child(conn, current, name)? {
+            Some(id) => current = Some(id),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Follows `names` down from `root`, creating folders that don't exist yet.
+pub fn ensure_path(conn: &Connection, root: Option<i32>, names: &[&str]) -> Result<Option<i32>, String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut current = root;
+    for name in names {
+        current = Some(match child(conn, current, name)? {
+            Some(id) => id,
+            None => {
+                conn.execute(
+                    "INSERT INTO snippet_folders (name, parent_id, created_at, updated_at) VALUES (?, ?, ?, ?)",
+                    params![validate_name(name)?, current, now, now],
+                ).map_err(|e| format!("Database error: {}", e))?;
+                conn.last_insert_rowid() as i32
+            }
+        });
+    }
+    Ok(current)
+}
+
+// Snippet folder commands
+#[tauri::command]
+pub fn get_snippet_folders() -> Result<Vec<SnippetFolder>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let tree = load_tree(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, parent_id, created_at, updated_at,
+                (SELECT COUNT(*) FROM code_snippets WHERE code_snippets.folder_id = snippet_folders.id AND deleted_at IS NULL)
+         FROM snippet_folders",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut folders = stmt.query_map([], |row| {
+        Ok(SnippetFolder {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            parent_id: row.get(2)?,
+            path: String::new(),
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            snippet_count: row.get(5)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?
+        .collect::<Result<Vec<SnippetFolder>, _>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    for folder in &mut folders {
+        folder.path = path_names(&tree, folder.id.unwrap_or_default(), None).join("/");
+    }
+    // Sorting by path segments puts each folder right after its parent
+    folders.sort_by_cached_key(|folder| folder.path.to_lowercase().split('/').map(str::to_string).collect::<Vec<_>>());
+
+    Ok(folders)
+}
+
+/// Creates, renames or moves a folder; folder names are unique among their siblings.
+#[tauri::command]
+pub fn save_snippet_folder(folder: SnippetFolder) -> Result<i32, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let name = validate_name(&folder.name)?;
+    if let Some(parent_id) = folder.parent_id {
+        require_folder(conn, parent_id)?;
+        if let Some(id) = folder.id {
+            if subtree_ids(conn, id)?.contains(&parent_id) {
+                return Err("A folder can't be moved into itself or one of its subfolders".to_string());
+            }
+        }
+    }
+    if child(conn, folder.parent_id, &name)?.is_some_and(|existing| Some(existing) != folder.id) {
+        return Err(format!("A folder named '{}' already exists there", name));
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    match folder.id {
+        Some(id) => {
+            let updated = conn.execute(
+                "UPDATE snippet_folders SET name = ?, parent_id = ?, updated_at = ? WHERE id = ?",
+                params![name, folder.parent_id, now, id],
+            ).map_err(|e| format!("Database error: {}", e))?;
+            if updated == 0 {
+                return Err(format!("Snippet folder {} not found", id));
+            }
+            // Synced snippet files are laid out by folder path
+            crate::snippet_sync::record_change(conn);
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO snippet_folders (name, parent_id, created_at, updated_at) VALUES (?, ?, ?, ?)",
+                params![name, folder.parent_id, now, now],
+            ).map_err(|e| format!("Database error: {}", e))?;
+            Ok(conn.last_insert_rowid() as i32)
+        }
+    }
+}
+
+/// Deletes a folder, handing its snippets and subfolders to its parent.
+#[tauri::command]
+pub fn delete_snippet_folder(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let parent_id: Option<i32> = conn.query_row("SELECT parent_id FROM snippet_folders WHERE id = ?", params![id], |row| row.get(0))
+        .map_err(|_| format!("Snippet folder {} not found", id))?;
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+    merge_into(&tx, id, parent_id)?;
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    crate::snippet_sync::record_change(conn);
+
+    Ok(())
+}
+
+/// Moves everything in `source` into `target` (the top level for `None`) and removes `source`.
+/// Subfolders whose name is already taken in `target` are merged into that folder in turn.
+fn merge_into(conn: &Connection, source: i32, target: Option<i32>) -> Result<(), String> {
+    conn.execute("UPDATE code_snippets SET folder_id = ? WHERE folder_id = ?", params![target, source])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut stmt = conn.prepare("SELECT id, name FROM snippet_folders WHERE parent_id = ?")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let children = stmt.query_map(params![source], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Database error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+    drop(stmt);
+    for (child_id, name) in children {
+        match child(conn, target, &name)? {
+            Some(existing) => merge_into(conn, child_id, Some(existing))?,
+            None => {
+                conn.execute("UPDATE snippet_folders SET parent_id = ? WHERE id = ?", params![target, child_id])
+                    .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+    }
+
+    conn.execute("DELETE FROM snippet_folders WHERE id = ?", params![source])
+        .map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+/// Moves snippets into a folder, or to the top level for `None`; all of them move or none do.
+#[tauri::command]
+pub fn move_snippets_to_folder(ids: Vec<i32>, folder_id: Option<i32>) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if let Some(folder_id) = folder_id {
+        require_folder(conn, folder_id)?;
+    }
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+    let mut ids = ids;
+    ids.sort_unstable();
+    ids.dedup();
+    for id in &ids {
+        let updated = tx.execute(
+            "UPDATE code_snippets SET folder_id = ? WHERE id = ? AND deleted_at IS NULL",
+            params![folder_id, id],
+        ).map_err(|e| format!("Database error: {}", e))?;
+        if updated == 0 {
+            return Err(format!("Code snippet {} not found", id));
+        }
+    }
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    crate::snippet_sync::record_change(conn);
+
+    Ok(ids.len())
+}
@@ -0,0 +1,767 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::{highlight, snippet_folders, tags, DB};
+
+/// VS Code language ids that differ from ours, as (VS Code, ours).
+const VSCODE_LANGUAGES: [(&str, &str); 6] = [
+    ("shellscript", "bash"),
+    ("javascriptreact", "javascript"),
+    ("typescriptreact", "typescript"),
+    ("c", "cpp"),
+    ("jsonc", "json"),
+    ("plaintext", "text"),
+];
+
+/// A snippet as read from or written to a file, independent of the format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnippetDocument {
+    pub title: String,
+    pub description: String,
+    pub code: String,
+    /// `None` when the file doesn't say; detection fills it in on save.
+    pub language: Option<String>,
+    /// `None` when the format has no tags, so existing tags are left alone on overwrite.
+    pub tags: Option<Vec<String>>,
+    pub prefix: Option<String>,
+    /// Last edit time; written to front-matter so synced copies can tell which side changed.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetImportItem {
+    /// File the snippet came from, relative to the import path for folders.
+    source: String,
+    /// Subdirectory of a folder import the snippet sits in, which becomes its subfolder.
+    folder: Option<String>,
+    title: String,
+    language: Option<String>,
+    prefix: Option<String>,
+    /// `new`, `conflict` (a snippet with this title exists), `duplicate` (repeated within the import) or `error`.
+    status: String,
+    existing_id: Option<i32>,
+    error: Option<String>,
+    #[serde(skip)]
+    document: Option<SnippetDocument>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetImportPreview {
+    items: Vec<SnippetImportItem>,
+    new_count: usize,
+    conflict_count: usize,
+    error_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetImportResult {
+    imported: usize,
+    overwritten: usize,
+    skipped: usize,
+    errors: Vec<SnippetImportItem>,
+}
+
+/// One entry of a VS Code snippets file.
+#[derive(Debug, Serialize, Deserialize)]
+struct VscodeSnippet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<StringOrList>,
+    body: StringOrList,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum StringOrList {
+    Text(String),
+    List(Vec<String>),
+}
+
+impl StringOrList {
+    fn into_list(self) -> Vec<String> {
+        match self {
+            StringOrList::Text(text) => vec![text],
+            StringOrList::List(list) => list,
+        }
+    }
+}
+
+/// Metadata block at the top of a snippet file in the folder format.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FrontMatter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// Either a YAML list or a comma-separated string.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tags: Option<StringOrList>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<String>,
+}
+
+fn from_vscode_language(scope: &str) -> String {
+    let scope = scope.trim().to_lowercase();
+    VSCODE_LANGUAGES.iter()
+        .find(|(vscode, _)| *vscode == scope)
+        .map(|(_, ours)| ours.to_string())
+        .unwrap_or(scope)
+}
+
+fn to_vscode_language(language: &str) -> &str {
+    match language {
+        "bash" => "shellscript",
+        other => other,
+    }
+}
+
+/// Drops `//` and `/* */` comments and trailing commas, which VS Code allows in snippet files.
+fn strip_jsonc(text: &str) -> String {
+    // Comments go first so a comment between a trailing comma and the bracket doesn't hide it
+    let without_comments = strip_outside_strings(text, |chars, i| match (chars[i], chars.get(i + 1)) {
+        ('/', Some('/')) => chars[i..].iter().position(|&c| c == '\n').map_or(chars.len(), |offset| i + offset),
+        ('/', Some('*')) => chars[i + 2..].windows(2).position(|pair| pair == ['*', '/']).map_or(chars.len(), |offset| i + 2 + offset + 2),
+        _ => i,
+    });
+    strip_outside_strings(&without_comments, |chars, i| {
+        let trailing = chars[i] == ',' && matches!(chars[i + 1..].iter().find(|c| !c.is_whitespace()), Some('}' | ']'));
+        if trailing { i + 1 } else { i }
+    })
+}
+
+/// Copies `text`, letting `skip` return where to resume when it wants to drop text at `i` outside string literals.
+fn strip_outside_strings(text: &str, skip: impl Fn(&[char], usize) -> usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            out.push(c);
+            if c == '\\' {
+                if let Some(&next) = chars.get(i + 1) {
+                    out.push(next);
+                    i += 1;
+                }
+            } else if c == '"' {
+                in_string = false;
+            }
+            i += 1;
+            continue;
+        }
+
+        let resume = skip(&chars, i);
+        if resume > i {
+            i = resume;
+            continue;
+        }
+        in_string = c == '"';
+        out.push(c);
+        i += 1;
+    }
+    out
+}
+
+/// Reads a VS Code snippets file (`.code-snippets` or a per-language `.json`).
+pub fn parse_vscode(text: &str) -> Result<Vec<SnippetDocument>, String> {
+    // Titles are map keys and their file order isn't kept, so sort for a stable result
+    let snippets: HashMap<String, VscodeSnippet> = serde_json::from_str(&strip_jsonc(text))
+        .map_err(|e| format!("Invalid VS Code snippets file: {}", e))?;
+
+    let mut documents: Vec<SnippetDocument> = snippets.into_iter().map(|(title, snippet)| {
+        // A scope may list several languages; the first one is the snippet's language
+        let language = snippet.scope.as_deref()
+            .and_then(|scope| scope.split(',').map(str::trim).find(|scope| !scope.is_empty()))
+            .map(from_vscode_language);
+        let prefix = snippet.prefix.map(StringOrList::into_list).filter(|list| !list.is_empty()).map(|list| list.join(", "));
+        SnippetDocument {
+            title,
+            description: snippet.description.unwrap_or_default(),
+            code: snippet.body.into_list().join("\n"),
+            language,
+            tags: None,
+            prefix,
+            updated_at: None,
+        }
+    }).collect();
+    documents.sort_by_key(|document| document.title.to_lowercase());
+
+    Ok(documents)
+}
+
+/// Writes snippets as a VS Code snippets file; repeated titles get a ` (2)` suffix since titles are keys.
+pub fn to_vscode(documents: &[SnippetDocument]) -> Result<String, String> {
+    let mut snippets = serde_json::Map::new();
+    for document in documents {
+        let mut title = document.title.clone();
+        let mut n = 2;
+        while snippets.contains_key(&title) {
+            title = format!("{} ({})", document.title, n);
+            n += 1;
+        }
+
+        let prefixes: Vec<String> = document.prefix.as_deref().unwrap_or_default()
+            .split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect();
+        let language = document.language.as_deref().unwrap_or("text");
+        let snippet = VscodeSnippet {
+            prefix: match prefixes.len() {
+                0 => None,
+                1 => prefixes.into_iter().next().map(StringOrList::Text),
+                _ => Some(StringOrList::List(prefixes)),
+            },
+            body: StringOrList::List(document.code.split('\n').map(str::to_string).collect()),
+            description: Some(document.description.clone()).filter(|d| !d.is_empty()),
+            scope: Some(to_vscode_language(language).to_string()).filter(|_| language != "text"),
+        };
+        snippets.insert(title, serde_json::to_value(snippet).map_err(|e| format!("Serialization error: {}", e))?);
+    }
+
+    serde_json::to_string_pretty(&snippets).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Reads one snippet file; files without front-matter are taken whole, titled by their name.
+pub fn parse_front_matter(file_name: &str, text: &str) -> Result<SnippetDocument, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let (front, code) = match text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) {
+        Some(rest) => {
+            let end = ["\n---\n", "\n---\r\n"].iter()
+                .filter_map(|marker| rest.find(marker).map(|at| (at, marker.len())))
+                .min()
+                .or_else(|| rest.strip_suffix("\n---").map(|front| (front.len(), 4)))
+                .ok_or("Front-matter is not closed with ---")?;
+            let front: FrontMatter = serde_yaml::from_str(&rest[..end.0])
+                .map_err(|e| format!("Invalid front-matter: {}", e))?;
+            (front, &rest[(end.0 + end.1).min(rest.len())..])
+        }
+        None => (FrontMatter::default(), text),
+    };
+
+    let title = front.title
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| file_name.to_string());
+    let tags = front.tags.map(|tags| match tags {
+        StringOrList::Text(text) => tags::parse_tags(&text),
+        StringOrList::List(list) => tags::parse_tags(&list.join(",")),
+    });
+    let language = front.language
+        .filter(|language| !highlight::needs_detection(language))
+        .or_else(|| highlight::from_extension(file_name).map(str::to_string));
+
+    Ok(SnippetDocument {
+        title,
+        description: front.description.unwrap_or_default(),
+        code: code.to_string(),
+        language,
+        tags,
+        prefix: front.prefix.filter(|prefix| !prefix.trim().is_empty()),
+        updated_at: front.updated_at,
+    })
+}
+
+/// Renders a snippet as a front-matter file: metadata between `---` lines, then the code verbatim.
+pub fn render_front_matter(document: &SnippetDocument) -> Result<String, String> {
+    let front = FrontMatter {
+        title: Some(document.title.clone()),
+        description: Some(document.description.clone()).filter(|d| !d.is_empty()),
+        language: document.language.clone(),
+        tags: document.tags.clone().filter(|tags| !tags.is_empty()).map(StringOrList::List),
+        prefix: document.prefix.clone(),
+        updated_at: document.updated_at.clone(),
+    };
+    let yaml = serde_yaml::to_string(&front).map_err(|e| format!("Serialization error: {}", e))?;
+
+    Ok(format!("---\n{}---\n{}", yaml, document.code))
+}
+
+/// File name for a snippet: its slugged title plus the language's extension.
+pub fn file_name_for(document: &SnippetDocument) -> String {
+    let language = document.language.as_deref().unwrap_or("text");
+    let mut slug = String::new();
+    for c in document.title.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() || c == '_' || c == '.' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches(|c| c == '-' || c == '.');
+    let slug = if slug.is_empty() { "snippet" } else { slug };
+
+    // Titles that are already file names, like `docker-compose.yml`, keep their extension
+    if highlight::from_extension(slug) == Some(language) {
+        slug.to_string()
+    } else {
+        format!("{}.{}", slug, highlight::extension_for(language))
+    }
+}
+
+/// Makes `file_name` unique among `taken` by numbering it before the extension.
+pub fn unique_file_name(file_name: String, taken: &mut Vec<String>) -> String {
+    let (stem, extension) = file_name.rsplit_once('.').unwrap_or((&file_name, ""));
+    let mut candidate = file_name.clone();
+    let mut n = 2;
+    while taken.iter().any(|name| name.eq_ignore_ascii_case(&candidate)) {
+        candidate = if extension.is_empty() { format!("{}-{}", stem, n) } else { format!("{}-{}.{}", stem, n, extension) };
+        n += 1;
+    }
+    taken.push(candidate.clone());
+    candidate
+}
+
+/// A live snippet with where it's filed.
+pub struct StoredSnippet {
+    pub id: i32,
+    pub folder_id: Option<i32>,
+    pub document: SnippetDocument,
+}
+
+/// Live snippets as documents, ordered by title; `ids` limits them to a selection.
+pub fn load_documents(conn: &Connection, ids: Option<&[i32]>) -> Result<Vec<StoredSnippet>, String> {
+    let mut stmt = conn.prepare(concat!(
+        "SELECT id, title, description, code, COALESCE(language, 'text'), ",
+        tags::tag_names_sql!("snippet", "code_snippets.id"),
+        ", prefix, updated_at, folder_id FROM code_snippets WHERE deleted_at IS NULL ORDER BY title COLLATE NOCASE, id",
+    )).map_err(|e| format!("Database error: {}", e))?;
+
+    let snippets = stmt.query_map([], |row| {
+        let tag_names: String = row.get(5)?;
+        Ok(StoredSnippet {
+            id: row.get(0)?,
+            folder_id: row.get(8)?,
+            document: SnippetDocument {
+                title: row.get(1)?,
+                description: row.get(2)?,
+                code: row.get(3)?,
+                language: Some(row.get(4)?),
+                tags: Some(tags::parse_tags(&tag_names)),
+                prefix: row.get(6)?,
+                updated_at: row.get(7)?,
+            },
+        })
+    }).map_err(|e| format!("Database error: {}", e))?
+        .collect::<Result<Vec<StoredSnippet>, _>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(match ids {
+        Some(ids) => snippets.into_iter().filter(|snippet| ids.contains(&snippet.id)).collect(),
+        None => snippets,
+    })
+}
+
+/// Saves a document as a new snippet in `folder_id`, or over `id` where it already is, keeping
+/// existing tags when the document has none.
+pub fn write_document(conn: &Connection, id: Option<i32>, document: &SnippetDocument, folder_id: Option<i32>) -> Result<i32, String> {
+    let tags = match (&document.tags, id) {
+        (Some(tags), _) => tags.join(", "),
+        (None, Some(id)) => conn.query_row(
+            concat!("SELECT ", tags::tag_names_sql!("snippet", "code_snippets.id"), " FROM code_snippets WHERE id = ?"),
+            params![id],
+            |row| row.get(0),
+        ).map_err(|e| format!("Database error: {}", e))?,
+        (None, None) => String::new(),
+    };
+
+    crate::write_code_snippet(conn, &crate::CodeSnippet {
+        id,
+        title: document.title.clone(),
+        description: document.description.clone(),
+        code: document.code.clone(),
+        language: document.language.clone().unwrap_or_default(),
+        tags,
+        created_at: String::new(),
+        updated_at: String::new(),
+        expandable: false,
+        prefix: document.prefix.clone(),
+        folder_id,
+    })
+}
+
+/// `vscode` for a snippets file, `folder` for a directory of front-matter files.
+fn resolve_format(path: &Path, format: Option<String>) -> Result<String, String> {
+    let format = match format {
+        Some(format) => format.trim().to_lowercase(),
+        None if path.is_dir() => "folder".to_string(),
+        None => match path.extension().and_then(|e| e.to_str()) {
+            Some("json" | "code-snippets") => "vscode".to_string(),
+            _ => "folder".to_string(),
+        },
+    };
+    match format.as_str() {
+        "vscode" | "folder" => Ok(format),
+        other => Err(format!("Unknown snippet format '{}' (expected vscode or folder)", other)),
+    }
+}
+
+/// A snippet read from `source` (a `/`-separated path for folders), or why it couldn't be.
+type ParsedSnippet = (String, Result<SnippetDocument, String>);
+
+/// Front-matter files in `dir` and its subdirectories, skipping hidden ones. Symlinked files are
+/// read but symlinked directories aren't followed, so a link loop can't recurse forever.
+fn read_folder(dir: &Path, prefix: &str, files: &mut Vec<ParsedSnippet>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read folder: {}", e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read folder: {}", e))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let source = format!("{}{}", prefix, name);
+        if name.starts_with('.') {
+            continue;
+        }
+        let file_type = entry.file_type().map_err(|e| format!("Failed to read folder: {}", e))?;
+        if file_type.is_dir() {
+            read_folder(&entry.path(), &format!("{}/", source), files)?;
+            continue;
+        }
+        if file_type.is_symlink() && entry.path().is_dir() {
+            continue;
+        }
+        let document = std::fs::read_to_string(entry.path())
+            .map_err(|e| format!("Failed to read file: {}", e))
+            .and_then(|text| parse_front_matter(&name, &text));
+        files.push((source, document));
+    }
+    Ok(())
+}
+
+/// The folder part of a source path, as folder names.
+pub fn source_folders(source: &str) -> Vec<&str> {
+    let mut names: Vec<&str> = source.split('/').collect();
+    names.pop();
+    names
+}
+
+/// How `resolutions` refer to an item: its title, prefixed with its folder path when it has one.
+fn resolution_key(folder: Option<&str>, title: &str) -> String {
+    match folder {
+        Some(folder) => format!("{}/{}", folder, title.trim()).to_lowercase(),
+        None => title.trim().to_lowercase(),
+    }
+}
+
+fn build_preview(conn: &Connection, path: &Path, format: &str, folder_id: Option<i32>) -> Result<SnippetImportPreview, String> {
+    let parsed: Vec<ParsedSnippet> = if format == "vscode" {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let source = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        parse_vscode(&text)?.into_iter().map(|document| (source.clone(), Ok(document))).collect()
+    } else {
+        let mut files = Vec::new();
+        read_folder(path, "", &mut files)?;
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        files
+    };
+
+    let mut seen: Vec<String> = Vec::new();
+    let mut items = Vec::new();
+    for (source, document) in parsed {
+        // Only the folder format nests; a VS Code file's name is never a folder
+        let folders = if format == "folder" { source_folders(&source) } else { Vec::new() };
+        let folder = Some(folders.join("/")).filter(|folder| !folder.is_empty());
+        let document = match document {
+            Ok(document) => document,
+            Err(error) => {
+                items.push(SnippetImportItem {
+                    title: source.clone(),
+                    source,
+                    folder,
+                    language: None,
+                    prefix: None,
+                    status: "error".to_string(),
+                    existing_id: None,
+                    error: Some(error),
+                    document: None,
+                });
+                continue;
+            }
+        };
+
+        // Titles only clash within the folder the snippet would land in
+        let key = resolution_key(folder.as_deref(), &document.title);
+        let existing_id: Option<i32> = match snippet_folders::lookup_path(conn, folder_id, &folders)? {
+            Some(destination) => conn.query_row(
+                "SELECT id FROM code_snippets WHERE title = ? COLLATE NOCASE AND folder_id IS ? AND deleted_at IS NULL ORDER BY id LIMIT 1",
+                params![document.title.trim(), destination],
+                |row| row.get(0),
+            ).ok(),
+            None => None,
+        };
+        let status = if seen.contains(&key) {
+            "duplicate"
+        } else if existing_id.is_some() {
+            "conflict"
+        } else {
+            "new"
+        };
+        seen.push(key);
+
+        items.push(SnippetImportItem {
+            source,
+            folder,
+            title: document.title.clone(),
+            language: document.language.clone(),
+            prefix: document.prefix.clone(),
+            status: status.to_string(),
+            existing_id,
+            error: None,
+            document: Some(document),
+        });
+    }
+
+    let count = |status: &str| items.iter().filter(|item| item.status == status).count();
+    Ok(SnippetImportPreview {
+        new_count: count("new"),
+        conflict_count: count("conflict"),
+        error_count: count("error"),
+        items,
+    })
+}
+
+fn conflict_action(action: &str) -> Result<&'static str, String> {
+    match action.trim().to_lowercase().as_str() {
+        "skip" => Ok("skip"),
+        "overwrite" => Ok("overwrite"),
+        other => Err(format!("Unknown conflict action '{}' (expected skip or overwrite)", other)),
+    }
+}
+
+// Snippet import/export commands
+#[tauri::command]
+pub fn preview_snippet_import(file_path: String, format: Option<String>, folder_id: Option<i32>) -> Result<SnippetImportPreview, String> {
+    let path = Path::new(&file_path);
+    let format = resolve_format(path, format)?;
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if let Some(folder_id) = folder_id {
+        snippet_folders::require_folder(conn, folder_id)?;
+    }
+    build_preview(conn, path, &format, folder_id)
+}
+
+/// Imports snippets into `folder_id` (the top level by default), recreating a folder import's
+/// subdirectories as subfolders. Conflicts with existing titles in the same folder follow
+/// `on_conflict` (`skip` by default); `resolutions` overrides that per title, written as
+/// `sub/folder/title` for snippets in subdirectories.
+#[tauri::command]
+pub fn commit_snippet_import(
+    file_path: String,
+    format: Option<String>,
+    folder_id: Option<i32>,
+    on_conflict: Option<String>,
+    resolutions: Option<HashMap<String, String>>,
+) -> Result<SnippetImportResult, String> {
+    let path = Path::new(&file_path);
+    let format = resolve_format(path, format)?;
+    let default_action = conflict_action(on_conflict.as_deref().unwrap_or("skip"))?;
+    let mut actions = HashMap::new();
+    for (key, action) in resolutions.unwrap_or_default() {
+        actions.insert(key.trim().to_lowercase(), conflict_action(&action)?);
+    }
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if let Some(folder_id) = folder_id {
+        snippet_folders::require_folder(conn, folder_id)?;
+    }
+    let preview = build_preview(conn, path, &format, folder_id)?;
+
+    // Either every chosen snippet lands or none do
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let (mut imported, mut overwritten, mut skipped) = (0, 0, 0);
+    let mut errors = Vec::new();
+    for item in preview.items {
+        let Some(document) = &item.document else {
+            errors.push(item);
+            continue;
+        };
+        let key = resolution_key(item.folder.as_deref(), &item.title);
+        match item.status.as_str() {
+            "new" => {
+                let folders: Vec<&str> = item.folder.as_deref().map(|folder| folder.split('/').collect()).unwrap_or_default();
+                let destination = snippet_folders::ensure_path(&tx, folder_id, &folders)?;
+                write_document(&tx, None, document, destination).map_err(|e| format!("{}: {}", item.source, e))?;
+                imported += 1;
+            }
+            "conflict" if actions.get(&key).copied().unwrap_or(default_action) == "overwrite" => {
+                write_document(&tx, item.existing_id, document, None).map_err(|e| format!("{}: {}", item.source, e))?;
+                overwritten += 1;
+            }
+            _ => skipped += 1,
+        }
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    crate::snippet_sync::record_change(conn);
+
+    Ok(SnippetImportResult { imported, overwritten, skipped, errors })
+}
+
+/// Exports snippets to a VS Code snippets file or a folder of front-matter files. `folder_id`
+/// limits the export to that folder's subtree, which the folder format lays out as
+/// subdirectories; `ids` narrows it to a selection.
+#[tauri::command]
+pub fn export_snippets(file_path: String, format: Option<String>, ids: Option<Vec<i32>>, folder_id: Option<i32>) -> Result<usize, String> {
+    let path = Path::new(&file_path);
+    let format = resolve_format(path, format)?;
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if let Some(folder_id) = folder_id {
+        snippet_folders::require_folder(conn, folder_id)?;
+    }
+    let folder_paths = snippet_folders::relative_paths(conn, folder_id)?;
+    let snippets: Vec<StoredSnippet> = load_documents(conn, ids.as_deref())?.into_iter()
+        .filter(|snippet| match snippet.folder_id {
+            Some(id) => folder_paths.contains_key(&id),
+            None => folder_id.is_none(),
+        })
+        .collect();
+    drop(db_guard);
+
+    if format == "vscode" {
+        let documents: Vec<SnippetDocument> = snippets.into_iter().map(|snippet| snippet.document).collect();
+        std::fs::write(path, to_vscode(&documents)?).map_err(|e| format!("Failed to write file: {}", e))?;
+        return Ok(documents.len());
+    }
+
+    let mut taken: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    for snippet in &snippets {
+        let mut dir = path.to_path_buf();
+        for name in snippet.folder_id.and_then(|id| folder_paths.get(&id)).into_iter().flatten() {
+            dir.push(name);
+        }
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+        let name = unique_file_name(file_name_for(&snippet.document), taken.entry(dir.clone()).or_default());
+        std::fs::write(dir.join(name), render_front_matter(&snippet.document)?)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+
+    Ok(snippets.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(title: &str, language: Option<&str>) -> SnippetDocument {
+        SnippetDocument {
+            title: title.to_string(),
+            description: String::new(),
+            code: String::new(),
+            language: language.map(str::to_string),
+            tags: None,
+            prefix: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn strip_jsonc_drops_comments_and_trailing_commas() {
+        let text = "{\n  // note\n  \"a\": \"x // not a comment, }\", /* block */\n  \"b\": [1, 2, /* c */],\n}";
+        let value: serde_json::Value = serde_json::from_str(&strip_jsonc(text)).unwrap();
+        assert_eq!(value, serde_json::json!({"a": "x // not a comment, }", "b": [1, 2]}));
+        assert_eq!(strip_jsonc("\"a\\\",/*b*/\""), "\"a\\\",/*b*/\"");
+    }
+
+    #[test]
+    fn vscode_files_round_trip() {
+        let text = r#"{
+            "Log": { "prefix": ["log", "cl"], "body": ["console.log($1);", "$0"], "description": "Log it", "scope": "javascript,typescript" },
+            "Shell": { "prefix": "sh", "body": "echo hi", "scope": "shellscript" },
+        }"#;
+        let documents = parse_vscode(text).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].title, "Log");
+        assert_eq!(documents[0].code, "console.log($1);\n$0");
+        assert_eq!(documents[0].prefix.as_deref(), Some("log, cl"));
+        assert_eq!(documents[0].language.as_deref(), Some("javascript"));
+        assert_eq!(documents[1].language.as_deref(), Some("bash"));
+
+        let again = parse_vscode(&to_vscode(&documents).unwrap()).unwrap();
+        let summary = |docs: &[SnippetDocument]| docs.iter().map(|d| (d.title.clone(), d.code.clone(), d.prefix.clone(), d.language.clone())).collect::<Vec<_>>();
+        assert_eq!(summary(&again), summary(&documents));
+    }
+
+    #[test]
+    fn front_matter_round_trips() {
+        let original = SnippetDocument {
+            title: "Deploy: prod".to_string(),
+            description: "Ships it".to_string(),
+            code: "---\nkubectl apply -f k8s/\n".to_string(),
+            language: Some("bash".to_string()),
+            tags: Some(vec!["ops".to_string(), "k8s".to_string()]),
+            prefix: Some("deploy".to_string()),
+            updated_at: Some("2024-05-01T10:00:00+00:00".to_string()),
+        };
+        let text = render_front_matter(&original).unwrap();
+        let parsed = parse_front_matter("deploy-prod.sh", &text).unwrap();
+        assert_eq!(render_front_matter(&parsed).unwrap(), text);
+        assert_eq!(parsed.code, original.code);
+        assert_eq!(parsed.tags, original.tags);
+    }
+
+    #[test]
+    fn front_matter_is_optional() {
+        let plain = parse_front_matter("notes.md", "# Title\nbody").unwrap();
+        assert_eq!((plain.title.as_str(), plain.code.as_str(), plain.language.as_deref()), ("notes.md", "# Title\nbody", Some("markdown")));
+
+        let crlf = parse_front_matter("x.txt", "\u{feff}---\r\ntitle: X\r\ntags: a, b\r\nlanguage: auto\r\n---\r\ncode").unwrap();
+        assert_eq!((crlf.title.as_str(), crlf.code.as_str()), ("X", "code"));
+        assert_eq!(crlf.tags, Some(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(crlf.language.as_deref(), Some("text"));
+
+        assert!(parse_front_matter("x", "---\ntitle: X\ncode").is_err());
+    }
+
+    #[test]
+    fn file_names_come_from_titles() {
+        assert_eq!(file_name_for(&document("Git: undo last commit!", Some("bash"))), "git-undo-last-commit.sh");
+        assert_eq!(file_name_for(&document("docker-compose.yml", Some("yaml"))), "docker-compose.yml");
+        assert_eq!(file_name_for(&document("  ...  ", None)), "snippet.txt");
+        assert_eq!(file_name_for(&document("Grüße", Some("python"))), "grüße.py");
+    }
+
+    #[test]
+    fn unique_file_names_number_repeats() {
+        let mut taken = vec!["a.rs".to_string()];
+        assert_eq!(unique_file_name("A.rs".to_string(), &mut taken), "A-2.rs");
+        assert_eq!(unique_file_name("a.rs".to_string(), &mut taken), "a-3.rs");
+        assert_eq!(unique_file_name("Makefile".to_string(), &mut taken), "Makefile");
+        assert_eq!(unique_file_name("Makefile".to_string(), &mut taken), "Makefile-2");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_folder_does_not_follow_directory_links() {
+        let dir = std::env::temp_dir().join(format!("snippet-io-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub/a.sh"), "echo a").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("sub/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("sub/a.sh"), dir.join("b.sh")).unwrap();
+
+        let mut files = Vec::new();
+        let result = read_folder(&dir, "", &mut files);
+        std::fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+
+        let mut sources: Vec<&str> = files.iter().map(|(source, _)| source.as_str()).collect();
+        sources.sort();
+        assert_eq!(sources, vec!["b.sh", "sub/a.sh"]);
+    }
+}
@@ -0,0 +1,690 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::snippet_io::{self, SnippetDocument};
+use crate::{snippet_folders, trash, DB};
+
+/// Folder inside the working tree that holds the snippet files unless another is chosen.
+const DEFAULT_DIRECTORY: &str = "snippets";
+
+/// Serializes git runs in the working tree. They happen without the database lock held, since
+/// hooks, signing prompts and network round trips can take arbitrarily long.
+static GIT: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetSyncStatus {
+    configured: bool,
+    repo_path: Option<String>,
+    directory: Option<String>,
+    last_synced_at: Option<String>,
+    /// Why the last automatic commit failed; cleared once one succeeds.
+    last_error: Option<String>,
+}
+
+/// A snippet and its file that both changed since they were last in step.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncConflict {
+    file_name: String,
+    snippet_id: Option<i32>,
+    title: String,
+    /// `None` when the snippet was deleted in the app.
+    local_updated_at: Option<String>,
+    /// `None` when the file was deleted from the working tree.
+    remote_updated_at: Option<String>,
+}
+
+/// Changes applied to the receiving side: files when committing, snippets when pulling.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    created: usize,
+    updated: usize,
+    deleted: usize,
+    committed: bool,
+    conflicts: Vec<SyncConflict>,
+}
+
+struct SyncConfig {
+    repo: PathBuf,
+    directory: String,
+}
+
+impl SyncConfig {
+    fn dir(&self) -> PathBuf {
+        self.repo.join(&self.directory)
+    }
+}
+
+/// What a file and its snippet looked like when they were last in step.
+struct SyncedState {
+    snippet_id: i32,
+    content_hash: String,
+    updated_at: String,
+}
+
+struct LocalSnippet {
+    id: i32,
+    /// Its folder path, `/`-separated; empty at the top level.
+    dir: String,
+    document: SnippetDocument,
+    /// The snippet rendered as its file would be.
+    content: String,
+}
+
+/// A snippet file paired with the snippet it mirrors; either side may be missing.
+struct SyncEntry {
+    /// Path below the sync folder, with a subdirectory per snippet folder.
+    file_name: String,
+    state: Option<SyncedState>,
+    local: Option<LocalSnippet>,
+    remote: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    /// Snippets out to files.
+    Commit,
+    /// Files back into snippets.
+    Pull,
+}
+
+impl SyncEntry {
+    fn local_changed(&self) -> bool {
+        match (&self.local, &self.state) {
+            // Tag renames and merges change the file without touching `updated_at`, so only the content counts
+            (Some(local), Some(state)) => content_hash(&local.content) != state.content_hash,
+            (None, None) => false,
+            _ => true,
+        }
+    }
+
+    fn remote_changed(&self) -> bool {
+        match (&self.remote, &self.state) {
+            (Some(text), Some(state)) => content_hash(text) != state.content_hash,
+            (None, None) => false,
+            _ => true,
+        }
+    }
+
+    fn in_agreement(&self) -> bool {
+        match (&self.local, &self.remote) {
+            (Some(local), Some(remote)) => local.content == *remote,
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn conflict(&self) -> SyncConflict {
+        let remote = self.remote.as_deref().and_then(|text| snippet_io::parse_front_matter(base_name(&self.file_name), text).ok());
+        SyncConflict {
+            file_name: self.file_name.clone(),
+            snippet_id: self.local.as_ref().map(|local| local.id),
+            title: self.local.as_ref().map(|local| local.document.title.clone())
+                .or_else(|| remote.as_ref().map(|document| document.title.clone()))
+                .unwrap_or_else(|| self.file_name.clone()),
+            local_updated_at: self.local.as_ref().and_then(|local| local.document.updated_at.clone()),
+            remote_updated_at: remote.and_then(|document| document.updated_at),
+        }
+    }
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snippet_sync (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            repo_path TEXT NOT NULL,
+            directory TEXT NOT NULL,
+            last_synced_at TEXT,
+            last_error TEXT
+        )",
+        [],
+    )?;
+
+    // One row per file, recording both sides as of the last sync
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS snippet_sync_files (
+            file_name TEXT PRIMARY KEY,
+            snippet_id INTEGER NOT NULL,
+            content_hash TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+fn content_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<Output, String> {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))
+}
+
+fn git_ok(repo: &Path, args: &[&str]) -> Result<String, String> {
+    let output = git(repo, args)?;
+    if !output.status.success() {
+        return Err(format!("git {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn has_upstream(repo: &Path) -> Result<bool, String> {
+    Ok(git(repo, &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"])?.status.success())
+}
+
+fn load_config(conn: &Connection) -> Result<Option<SyncConfig>, String> {
+    conn.query_row("SELECT repo_path, directory FROM snippet_sync WHERE id = 1", [], |row| {
+        Ok(SyncConfig { repo: PathBuf::from(row.get::<_, String>(0)?), directory: row.get(1)? })
+    }).optional().map_err(|e| format!("Database error: {}", e))
+}
+
+fn require_config(conn: &Connection) -> Result<SyncConfig, String> {
+    load_config(conn)?.ok_or_else(|| "Snippet sync is not configured".to_string())
+}
+
+/// The sync settings, holding the database lock only while reading them.
+fn current_config() -> Result<SyncConfig, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+    require_config(conn)
+}
+
+fn save_state(conn: &Connection, file_name: &str, snippet_id: i32, hash: &str, updated_at: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO snippet_sync_files (file_name, snippet_id, content_hash, updated_at) VALUES (?, ?, ?, ?)",
+        params![file_name, snippet_id, hash, updated_at],
+    ).map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+fn clear_state(conn: &Connection, file_name: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM snippet_sync_files WHERE file_name = ?", params![file_name])
+        .map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+/// The file name part of a synced file's path.
+fn base_name(file_name: &str) -> &str {
+    file_name.rsplit('/').next().unwrap_or(file_name)
+}
+
+/// The folder part of a synced file's path, `/`-separated.
+fn parent_dir(file_name: &str) -> String {
+    snippet_io::source_folders(file_name).join("/")
+}
+
+/// Files below `dir` keyed by their `/`-separated path. Hidden files and `.git` are skipped, and
+/// symlinked directories aren't followed.
+fn read_tree(dir: &Path, prefix: &str, files: &mut HashMap<String, String>) -> Result<(), String> {
+    for entry in std::fs::read_dir(dir).map_err(|e| format!("Failed to read folder: {}", e))? {
+        let entry = entry.map_err(|e| format!("Failed to read folder: {}", e))?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = format!("{}{}", prefix, name);
+        let file_type = entry.file_type().map_err(|e| format!("Failed to read folder: {}", e))?;
+        // Hidden folders may still be snippet folders, so only `.git` is left out
+        if file_type.is_dir() {
+            if name != ".git" {
+                read_tree(&entry.path(), &format!("{}/", path), files)?;
+            }
+            continue;
+        }
+        if name.starts_with('.') || !entry.path().is_file() {
+            continue;
+        }
+        let text = std::fs::read_to_string(entry.path()).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        files.insert(path, text);
+    }
+    Ok(())
+}
+
+/// Pairs every snippet file with its snippet, by sync history first and then by folder and title.
+fn collect_entries(conn: &Connection, config: &SyncConfig) -> Result<Vec<SyncEntry>, String> {
+    let mut stmt = conn.prepare("SELECT file_name, snippet_id, content_hash, updated_at FROM snippet_sync_files ORDER BY file_name")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let states = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, SyncedState { snippet_id: row.get(1)?, content_hash: row.get(2)?, updated_at: row.get(3)? }))
+    }).map_err(|e| format!("Database error: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let folder_paths = snippet_folders::relative_paths(conn, None)?;
+    let mut locals: HashMap<i32, LocalSnippet> = HashMap::new();
+    for snippet in snippet_io::load_documents(conn, None)? {
+        let dir = snippet.folder_id.and_then(|id| folder_paths.get(&id)).map(|names| names.join("/")).unwrap_or_default();
+        let content = snippet_io::render_front_matter(&snippet.document)?;
+        locals.insert(snippet.id, LocalSnippet { id: snippet.id, dir, document: snippet.document, content });
+    }
+
+    let mut remotes: HashMap<String, String> = HashMap::new();
+    let dir = config.dir();
+    if dir.is_dir() {
+        read_tree(&dir, "", &mut remotes)?;
+    }
+
+    let mut entries = Vec::new();
+    for (file_name, state) in states {
+        let remote = remotes.remove(&file_name);
+        let mut local = locals.remove(&state.snippet_id);
+        // A snippet moved to another folder gets a new file there and this one goes, unless the
+        // file changed too and the pair has to be settled where it is
+        let moved = local.as_ref().is_some_and(|local| !local.dir.eq_ignore_ascii_case(&parent_dir(&file_name)));
+        if moved && remote.as_deref().is_some_and(|text| content_hash(text) == state.content_hash) {
+            if let Some(local) = local.take() {
+                locals.insert(local.id, local);
+            }
+        }
+        entries.push(SyncEntry { file_name, state: Some(state), local, remote });
+    }
+
+    // Files and snippets never synced before pair up by title, e.g. when linking a tree that already has them
+    let mut new_files: Vec<(String, String)> = remotes.into_iter().collect();
+    new_files.sort();
+    for (file_name, text) in new_files {
+        let dir = parent_dir(&file_name);
+        let title = snippet_io::parse_front_matter(base_name(&file_name), &text).ok().map(|document| document.title.to_lowercase());
+        let local_id = title.and_then(|title| {
+            locals.values()
+                .filter(|local| local.dir.eq_ignore_ascii_case(&dir) && local.document.title.to_lowercase() == title)
+                .map(|local| local.id)
+                .min()
+        });
+        entries.push(SyncEntry {
+            file_name,
+            state: None,
+            local: local_id.and_then(|id| locals.remove(&id)),
+            remote: Some(text),
+        });
+    }
+
+    let mut taken: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in &entries {
+        taken.entry(parent_dir(&entry.file_name).to_lowercase()).or_default().push(base_name(&entry.file_name).to_string());
+    }
+    let mut new_snippets: Vec<LocalSnippet> = locals.into_values().collect();
+    new_snippets.sort_by_key(|local| local.id);
+    for local in new_snippets {
+        let name = snippet_io::unique_file_name(snippet_io::file_name_for(&local.document), taken.entry(local.dir.to_lowercase()).or_default());
+        entries.push(SyncEntry {
+            file_name: if local.dir.is_empty() { name } else { format!("{}/{}", local.dir, name) },
+            state: None,
+            local: Some(local),
+            remote: None,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Writes the snippet's side over its file, or removes the file if the snippet is gone.
+fn write_file(dir: &Path, entry: &SyncEntry) -> Result<(), String> {
+    let path = dir.join(&entry.file_name);
+    match &entry.local {
+        Some(local) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create folder: {}", e))?;
+            }
+            std::fs::write(&path, &local.content).map_err(|e| format!("Failed to write {}: {}", entry.file_name, e))
+        }
+        None if path.exists() => {
+            std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", entry.file_name, e))?;
+            // Drop subdirectories the removal left empty
+            for parent in path.ancestors().skip(1).take_while(|parent| *parent != dir) {
+                if std::fs::remove_dir(parent).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Records the snippet's side as what its file now holds.
+fn record_written(conn: &Connection, entry: &SyncEntry) -> Result<(), String> {
+    match &entry.local {
+        Some(local) => {
+            let updated_at = local.document.updated_at.clone().unwrap_or_default();
+            save_state(conn, &entry.file_name, local.id, &content_hash(&local.content), &updated_at)
+        }
+        None => clear_state(conn, &entry.file_name),
+    }
+}
+
+/// Puts back the sync state an entry had before a write that didn't happen.
+fn restore_state(conn: &Connection, entry: &SyncEntry) -> Result<(), String> {
+    match &entry.state {
+        Some(state) => save_state(conn, &entry.file_name, state.snippet_id, &state.content_hash, &state.updated_at),
+        None => clear_state(conn, &entry.file_name),
+    }
+}
+
+/// Applies the file's side to its snippet, or trashes the snippet if the file is gone.
+fn apply_file(conn: &Connection, entry: &SyncEntry) -> Result<(), String> {
+    let Some(text) = &entry.remote else {
+        if let Some(local) = &entry.local {
+            trash::move_to_trash(conn, "snippet", local.id)?;
+        }
+        return clear_state(conn, &entry.file_name);
+    };
+
+    let document = snippet_io::parse_front_matter(base_name(&entry.file_name), text)
+        .map_err(|e| format!("{}: {}", entry.file_name, e))?;
+    // New files are filed by their subdirectory; existing snippets stay where they are
+    let folder_id = match &entry.local {
+        Some(_) => None,
+        None => snippet_folders::ensure_path(conn, None, &snippet_io::source_folders(&entry.file_name))?,
+    };
+    let id = snippet_io::write_document(conn, entry.local.as_ref().map(|local| local.id), &document, folder_id)?;
+    // Keep the file's timestamp so the snippet doesn't look edited here
+    if let Some(updated_at) = &document.updated_at {
+        conn.execute("UPDATE code_snippets SET updated_at = ? WHERE id = ?", params![updated_at, id])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+    let updated_at: String = conn.query_row("SELECT updated_at FROM code_snippets WHERE id = ?", params![id], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    save_state(conn, &entry.file_name, id, &content_hash(text), &updated_at)
+}
+
+/// Carries changes one way. Pairs where both sides changed differently are reported, not touched.
+fn reconcile(conn: &Connection, config: &SyncConfig, direction: Direction) -> Result<SyncReport, String> {
+    let dir = config.dir();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create folder: {}", e))?;
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut report = SyncReport::default();
+    // Files are only touched once the database side is committed, so a failure can't leave the
+    // working tree ahead of the recorded state
+    let mut writes = Vec::new();
+    for entry in collect_entries(&tx, config)? {
+        let (local_changed, remote_changed) = (entry.local_changed(), entry.remote_changed());
+        if !local_changed && !remote_changed {
+            continue;
+        }
+        if entry.in_agreement() {
+            match &entry.local {
+                Some(local) => save_state(&tx, &entry.file_name, local.id, &content_hash(&local.content), local.document.updated_at.as_deref().unwrap_or_default())?,
+                None => clear_state(&tx, &entry.file_name)?,
+            }
+            continue;
+        }
+        if local_changed && remote_changed {
+            report.conflicts.push(entry.conflict());
+            continue;
+        }
+
+        let (source, target) = match direction {
+            Direction::Commit if local_changed => {
+                record_written(&tx, &entry)?;
+                (entry.local.is_some(), entry.remote.is_some())
+            }
+            Direction::Pull if remote_changed => {
+                apply_file(&tx, &entry)?;
+                (entry.remote.is_some(), entry.local.is_some())
+            }
+            _ => continue,
+        };
+        match (source, target) {
+            (true, false) => report.created += 1,
+            (true, true) => report.updated += 1,
+            (false, _) => report.deleted += 1,
+        }
+        if direction == Direction::Commit {
+            writes.push(entry);
+        }
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+
+    for (index, entry) in writes.iter().enumerate() {
+        if let Err(error) = write_file(&dir, entry) {
+            // Files not written yet keep their old state so the next sync tries them again
+            for unwritten in &writes[index..] {
+                restore_state(conn, unwritten)?;
+            }
+            return Err(error);
+        }
+    }
+    Ok(report)
+}
+
+/// Commits the snippet folder if anything in it changed, describing `report` in the message.
+/// Returns whether a commit was made. Must be called without the database lock held.
+fn git_commit(config: &SyncConfig, report: &SyncReport) -> Result<bool, String> {
+    let _git = GIT.lock().unwrap();
+
+    git_ok(&config.repo, &["add", "-A", "--", &config.directory])?;
+    let staged = !git(&config.repo, &["diff", "--cached", "--quiet", "--", &config.directory])?.status.success();
+    if staged {
+        let counts: Vec<String> = [(report.created, "added"), (report.updated, "updated"), (report.deleted, "removed")].iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect();
+        let message = if counts.is_empty() { "Sync snippets".to_string() } else { format!("Sync snippets: {}", counts.join(", ")) };
+        git_ok(&config.repo, &["commit", "-q", "-m", &message, "--", &config.directory])?;
+    }
+
+    Ok(staged)
+}
+
+/// Writes changed snippets to the working tree under the database lock, then releases it to
+/// commit them. Fails only if the working tree couldn't be brought up to date.
+fn commit_changes(config: &SyncConfig) -> Result<SyncReport, String> {
+    let mut report = {
+        let db_guard = DB.lock().unwrap();
+        let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+        reconcile(conn, config, Direction::Commit)?
+    };
+
+    let result = git_commit(config, &report);
+    record_outcome(&result);
+    report.committed = result?;
+    Ok(report)
+}
+
+/// Stores how a commit went for the status view, taking the database lock to do so.
+fn record_outcome(result: &Result<bool, String>) {
+    let db_guard = DB.lock().unwrap();
+    let Some(conn) = db_guard.as_ref() else { return };
+    let recorded = match result {
+        Ok(_) => mark_synced(conn),
+        Err(error) => record_error(conn, error),
+    };
+    if let Err(e) = recorded {
+        eprintln!("Failed to record snippet sync result: {}", e);
+    }
+}
+
+fn record_error(conn: &Connection, error: &str) -> Result<(), String> {
+    conn.execute("UPDATE snippet_sync SET last_error = ? WHERE id = 1", params![error])
+        .map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+fn mark_synced(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "UPDATE snippet_sync SET last_synced_at = ?, last_error = NULL WHERE id = 1",
+        params![chrono::Utc::now().to_rfc3339()],
+    ).map_err(|e| format!("Database error: {}", e))?;
+    Ok(())
+}
+
+/// Commits snippet changes when sync is set up. Files are written straight away; the git commit
+/// runs on its own thread once the caller releases the database. Failures are kept for the
+/// status view rather than failing the edit that triggered them.
+pub fn record_change(conn: &Connection) {
+    let config = match load_config(conn) {
+        Ok(Some(config)) => config,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Failed to load snippet sync settings: {}", e);
+            return;
+        }
+    };
+
+    match reconcile(conn, &config, Direction::Commit) {
+        Ok(report) => {
+            std::thread::spawn(move || record_outcome(&git_commit(&config, &report)));
+        }
+        Err(error) => {
+            if let Err(e) = record_error(conn, &error) {
+                eprintln!("Failed to record snippet sync error: {}", e);
+            }
+        }
+    }
+}
+
+fn status(conn: &Connection) -> Result<SnippetSyncStatus, String> {
+    let row = conn.query_row(
+        "SELECT repo_path, directory, last_synced_at, last_error FROM snippet_sync WHERE id = 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).optional().map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(match row {
+        Some((repo_path, directory, last_synced_at, last_error)) => SnippetSyncStatus {
+            configured: true,
+            repo_path: Some(repo_path),
+            directory: Some(directory),
+            last_synced_at,
+            last_error,
+        },
+        None => SnippetSyncStatus { configured: false, repo_path: None, directory: None, last_synced_at: None, last_error: None },
+    })
+}
+
+// Snippet sync commands
+#[tauri::command]
+pub fn get_snippet_sync_status() -> Result<SnippetSyncStatus, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    status(conn)
+}
+
+/// Points sync at a git working tree; snippets live in `directory` (default `snippets`) inside it.
+#[tauri::command]
+pub fn configure_snippet_sync(repo_path: String, directory: Option<String>) -> Result<SnippetSyncStatus, String> {
+    let repo = PathBuf::from(repo_path.trim());
+    if git_ok(&repo, &["rev-parse", "--is-inside-work-tree"]).ok().as_deref() != Some("true") {
+        return Err(format!("{} is not a git working tree", repo.display()));
+    }
+    let directory = directory.as_deref().map(str::trim).filter(|d| !d.is_empty()).unwrap_or(DEFAULT_DIRECTORY).trim_matches('/').to_string();
+    if Path::new(&directory).is_absolute() || directory.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+        return Err(format!("'{}' must be a folder inside the working tree", directory));
+    }
+    let repo_path = repo.to_string_lossy().to_string();
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    // Sync history only means something for the tree it was recorded against
+    let unchanged: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM snippet_sync WHERE id = 1 AND repo_path = ? AND directory = ?",
+        params![repo_path, directory],
+        |row| row.get(0),
+    ).map_err(|e| format!("Database error: {}", e))?;
+    if !unchanged {
+        conn.execute("DELETE FROM snippet_sync_files", [])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    conn.execute(
+        "INSERT INTO snippet_sync (id, repo_path, directory) VALUES (1, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET repo_path = excluded.repo_path, directory = excluded.directory",
+        params![repo_path, directory],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    status(conn)
+}
+
+#[tauri::command]
+pub fn disable_snippet_sync() -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    conn.execute("DELETE FROM snippet_sync_files", [])
+        .map_err(|e| format!("Database error: {}", e))?;
+    conn.execute("DELETE FROM snippet_sync", [])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
+/// Commits pending snippet changes, and pushes to the upstream branch when `push` is set.
+#[tauri::command]
+pub fn commit_snippet_sync(push: Option<bool>) -> Result<SyncReport, String> {
+    let config = current_config()?;
+    let report = commit_changes(&config)?;
+    if push.unwrap_or(false) {
+        let _git = GIT.lock().unwrap();
+        if !has_upstream(&config.repo)? {
+            return Err("The working tree's branch has no upstream to push to".to_string());
+        }
+        git_ok(&config.repo, &["push", "-q"])?;
+    }
+
+    Ok(report)
+}
+
+/// Brings changes in the working tree back into snippets, fast-forwarding from upstream first
+/// unless `fetch` is false. Local edits are committed beforehand so nothing is lost.
+#[tauri::command]
+pub fn pull_snippet_sync(fetch: Option<bool>) -> Result<SyncReport, String> {
+    let config = current_config()?;
+    let committed = commit_changes(&config)?.committed;
+    if fetch.unwrap_or(true) {
+        let _git = GIT.lock().unwrap();
+        if has_upstream(&config.repo)? {
+            git_ok(&config.repo, &["pull", "-q", "--ff-only"])?;
+        }
+    }
+
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let mut report = reconcile(conn, &config, Direction::Pull)?;
+    report.committed = committed;
+    mark_synced(conn)?;
+
+    Ok(report)
+}
+
+/// Settles a conflict by keeping one side: `local` (the snippet) or `remote` (the file).
+#[tauri::command]
+pub fn resolve_snippet_sync_conflict(file_name: String, keep: String) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let config = require_config(conn)?;
+    let entry = collect_entries(conn, &config)?.into_iter()
+        .find(|entry| entry.file_name == file_name)
+        .ok_or_else(|| format!("No synced snippet file named {}", file_name))?;
+
+    match keep.as_str() {
+        "local" => {
+            write_file(&config.dir(), &entry)?;
+            record_written(conn, &entry)?;
+            drop(db_guard);
+            commit_changes(&config)?;
+        }
+        "remote" => apply_file(conn, &entry)?,
+        other => return Err(format!("Unknown side '{}' (expected local or remote)", other)),
+    }
+
+    Ok(())
+}
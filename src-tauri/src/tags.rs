@@ -0,0 +1,243 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::DB;
+
+/// Taggable entities: `(kind, table)`.
+const TAGGABLE: [(&str, &str); 3] = [
+    ("note", "notes"),
+    ("snippet", "code_snippets"),
+    ("command", "command_templates"),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagCount {
+    id: i32,
+    name: String,
+    /// Live (not trashed) entities carrying the tag.
+    count: i64,
+    notes: i64,
+    snippets: i64,
+    commands: i64,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    let migrated: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'entity_tags'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS entity_tags (
+            tag_id INTEGER NOT NULL,
+            entity_kind TEXT NOT NULL,
+            entity_id INTEGER NOT NULL,
+            PRIMARY KEY (tag_id, entity_kind, entity_id)
+        )",
+        [],
+    )?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_entity_tags_entity ON entity_tags(entity_kind, entity_id)", [])?;
+
+    if !migrated {
+        migrate_comma_tags(conn)?;
+    }
+
+    Ok(())
+}
+
+// One-time move of the old comma-joined `tags` columns into the normalized tables. Notes never had one.
+fn migrate_comma_tags(conn: &Connection) -> SqliteResult<()> {
+    for (kind, table) in [("snippet", "code_snippets"), ("command", "command_templates")] {
+        let mut stmt = conn.prepare(&format!("SELECT id, tags FROM {} WHERE COALESCE(tags, '') != ''", table))?;
+        let rows: Vec<(i32, String)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<_>>()?;
+
+        for (id, tags) in rows {
+            write_entity_tags(conn, kind, id, &parse_tags(&tags))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a comma-separated tag string, dropping blanks and case-insensitive repeats.
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for tag in tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+        if !result.iter().any(|existing| existing.eq_ignore_ascii_case(tag)) {
+            result.push(tag.to_string());
+        }
+    }
+    result
+}
+
+/// SQL expression listing an entity's tags as `a, b, c`, given the kind and the outer query's id column.
+macro_rules! tag_names_sql {
+    ($kind:literal, $id_column:literal) => {
+        concat!(
+            "COALESCE((SELECT group_concat(name, ', ') FROM (SELECT tags.name FROM entity_tags JOIN tags ON tags.id = entity_tags.tag_id WHERE entity_tags.entity_kind = '",
+            $kind,
+            "' AND entity_tags.entity_id = ",
+            $id_column,
+            " ORDER BY tags.name COLLATE NOCASE)), '')"
+        )
+    };
+}
+pub(crate) use tag_names_sql;
+
+fn write_entity_tags(conn: &Connection, kind: &str, id: i32, names: &[String]) -> SqliteResult<()> {
+    conn.execute("DELETE FROM entity_tags WHERE entity_kind = ? AND entity_id = ?", params![kind, id])?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    for name in names {
+        conn.execute("INSERT OR IGNORE INTO tags (name, created_at) VALUES (?, ?)", params![name, now])?;
+        conn.execute(
+            "INSERT OR IGNORE INTO entity_tags (tag_id, entity_kind, entity_id) SELECT id, ?, ? FROM tags WHERE name = ?",
+            params![kind, id, name],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replaces an entity's tags. Names match existing tags case-insensitively, so "Rust" reuses "rust".
+pub fn set_entity_tags(conn: &Connection, kind: &str, id: i32, names: &[String]) -> Result<(), String> {
+    write_entity_tags(conn, kind, id, names)
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    remove_unused_tags(conn)
+}
+
+/// Drops an entity's tag rows, e.g. when it is purged from the trash.
+pub fn clear_entity_tags(conn: &Connection, kind: &str, id: i32) -> Result<(), String> {
+    conn.execute("DELETE FROM entity_tags WHERE entity_kind = ? AND entity_id = ?", params![kind, id])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    remove_unused_tags(conn)
+}
+
+fn remove_unused_tags(conn: &Connection) -> Result<(), String> {
+    conn.execute("DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM entity_tags)", [])
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(())
+}
+
+// Tag commands
+#[tauri::command]
+pub fn list_tags(kind: Option<String>) -> Result<Vec<TagCount>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if let Some(kind) = &kind {
+        if !TAGGABLE.iter().any(|(name, _)| name == kind) {
+            return Err(format!("Unknown tag kind '{}'", kind));
+        }
+    }
+
+    // Entities in the trash don't count towards a tag
+    let mut stmt = conn.prepare(
+        "SELECT tags.id, tags.name,
+                COUNT(live.entity_id),
+                COALESCE(SUM(live.entity_kind = 'note'), 0),
+                COALESCE(SUM(live.entity_kind = 'snippet'), 0),
+                COALESCE(SUM(live.entity_kind = 'command'), 0)
+         FROM tags LEFT JOIN (
+             SELECT tag_id, entity_kind, entity_id FROM entity_tags
+             WHERE (entity_kind = 'note' AND entity_id IN (SELECT id FROM notes WHERE deleted_at IS NULL))
+                OR (entity_kind = 'snippet' AND entity_id IN (SELECT id FROM code_snippets WHERE deleted_at IS NULL))
+                OR (entity_kind = 'command' AND entity_id IN (SELECT id FROM command_templates WHERE deleted_at IS NULL))
+         ) live ON live.tag_id = tags.id AND (?1 IS NULL OR live.entity_kind = ?1)
+         GROUP BY tags.id
+         HAVING ?1 IS NULL OR COUNT(live.entity_id) > 0
+         ORDER BY tags.name COLLATE NOCASE",
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    let tags = stmt.query_map(params![kind], |row| {
+        Ok(TagCount {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            count: row.get(2)?,
+            notes: row.get(3)?,
+            snippets: row.get(4)?,
+            commands: row.get(5)?,
+        })
+    }).map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for tag in tags {
+        result.push(tag.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn rename_tag(id: i32, name: String) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let name = name.trim();
+    if name.is_empty() || name.contains(',') {
+        return Err("Tag names must be non-empty and cannot contain commas".to_string());
+    }
+
+    let clash: Option<i32> = conn.query_row(
+        "SELECT id FROM tags WHERE name = ? AND id != ?",
+        params![name, id],
+        |row| row.get(0),
+    ).ok();
+    if clash.is_some() {
+        return Err(format!("A tag named '{}' already exists; merge the tags instead", name));
+    }
+
+    let updated = conn.execute("UPDATE tags SET name = ? WHERE id = ?", params![name, id])
+        .map_err(|e| format!("Database error: {}", e))?;
+    if updated == 0 {
+        return Err(format!("Tag {} not found", id));
+    }
+    // Snippet files list their tags by name
+    crate::snippet_sync::record_change(conn);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn merge_tags(source_ids: Vec<i32>, target_id: i32) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let exists: bool = conn.query_row("SELECT COUNT(*) > 0 FROM tags WHERE id = ?", params![target_id], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+    if !exists {
+        return Err(format!("Tag {} not found", target_id));
+    }
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut merged = 0;
+    for source_id in source_ids.into_iter().filter(|id| *id != target_id) {
+        tx.execute(
+            "INSERT OR IGNORE INTO entity_tags (tag_id, entity_kind, entity_id) SELECT ?, entity_kind, entity_id FROM entity_tags WHERE tag_id = ?",
+            params![target_id, source_id],
+        ).map_err(|e| format!("Database error: {}", e))?;
+        tx.execute("DELETE FROM entity_tags WHERE tag_id = ?", params![source_id])
+            .map_err(|e| format!("Database error: {}", e))?;
+        merged += tx.execute("DELETE FROM tags WHERE id = ?", params![source_id])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    crate::snippet_sync::record_change(conn);
+    Ok(merged)
+}
@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::DB;
+
+/// Placeholder types accepted in `{{name:type=default}}`.
+const PARAMETER_KINDS: [&str; 4] = ["string", "int", "float", "bool"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateParameter {
+    pub name: String,
+    pub kind: String,
+    pub default: Option<String>,
+    /// True when the placeholder has no default and a value must be supplied.
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Quote {
+    None,
+    Single,
+    Double,
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder { name: String, quote: Quote },
+}
+
+/// Splits a template into literal text and placeholders, noting the shell quoting each placeholder sits in.
+fn parse_template(command: &str) -> Result<(Vec<Segment<'_>>, Vec<TemplateParameter>), String> {
+    let mut segments = Vec::new();
+    let mut parameters: Vec<TemplateParameter> = Vec::new();
+    let mut quote = Quote::None;
+    let mut escaped = false;
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < command.len() {
+        let rest = &command[i..];
+        if rest.starts_with("{{") && !escaped {
+            let end = rest.find("}}")
+                .ok_or_else(|| format!("Unclosed placeholder at position {}", i))?;
+            // Anything that isn't placeholder-shaped, e.g. docker's `{{.State}}`, stays as written
+            let Some(parameter) = parse_placeholder(&rest[2..end])? else {
+                i += end + 2;
+                continue;
+            };
+
+            match parameters.iter().find(|existing| existing.name == parameter.name) {
+                Some(existing) if existing.kind != parameter.kind => {
+                    return Err(format!("Placeholder '{}' is used with different types", parameter.name));
+                }
+                Some(_) => {}
+                None => parameters.push(parameter.clone()),
+            }
+
+            segments.push(Segment::Text(&command[text_start..i]));
+            segments.push(Segment::Placeholder { name: parameter.name, quote });
+            i += end + 2;
+            text_start = i;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or_default();
+        match (quote, c) {
+            (_, _) if escaped => escaped = false,
+            (Quote::None | Quote::Double, '\\') => escaped = true,
+            (Quote::None, '\'') => quote = Quote::Single,
+            (Quote::Single, '\'') => quote = Quote::None,
+            (Quote::None, '"') => quote = Quote::Double,
+            (Quote::Double, '"') => quote = Quote::None,
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    segments.push(Segment::Text(&command[text_start..]));
+
+    Ok((segments, parameters))
+}
+
+fn parse_placeholder(inner: &str) -> Result<Option<TemplateParameter>, String> {
+    let (spec, default) = match inner.split_once('=') {
+        Some((spec, default)) => (spec, Some(default.to_string())),
+        None => (inner, None),
+    };
+    let (name, kind) = match spec.split_once(':') {
+        Some((name, kind)) => (name.trim(), kind.trim().to_lowercase()),
+        None => (spec.trim(), "string".to_string()),
+    };
+    // Kinds this version doesn't know were written for something else; they take plain text
+    let kind = if PARAMETER_KINDS.contains(&kind.as_str()) { kind } else { "string".to_string() };
+
+    let valid_name = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        return Ok(None);
+    }
+    if let Some(default) = &default {
+        validate_value(name, &kind, default)?;
+    }
+
+    Ok(Some(TemplateParameter {
+        name: name.to_string(),
+        kind,
+        required: default.is_none(),
+        default,
+    }))
+}
+
+/// Checks a value against its placeholder type, returning the normalized text.
+fn validate_value(name: &str, kind: &str, value: &str) -> Result<String, String> {
+    match kind {
+        "int" => value.trim().parse::<i64>()
+            .map(|v| v.to_string())
+            .map_err(|_| format!("'{}' must be a whole number, got '{}'", name, value)),
+        "float" => value.trim().parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(|_| value.trim().to_string())
+            .ok_or_else(|| format!("'{}' must be a number, got '{}'", name, value)),
+        "bool" => match value.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok("true".to_string()),
+            "false" | "no" | "0" => Ok("false".to_string()),
+            _ => Err(format!("'{}' must be true or false, got '{}'", name, value)),
+        },
+        _ => Ok(value.to_string()),
+    }
+}
+
+/// Quotes a value for the shell context its placeholder appears in.
+fn shell_quote(value: &str, quote: Quote) -> String {
+    match quote {
+        Quote::Single => value.replace('\'', "'\\''"),
+        Quote::Double => {
+            let mut quoted = String::with_capacity(value.len());
+            for c in value.chars() {
+                if matches!(c, '"' | '\\' | '$' | '`') {
+                    quoted.push('\\');
+                }
+                quoted.push(c);
+            }
+            quoted
+        }
+        Quote::None => {
+            let safe = !value.is_empty()
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+            if safe {
+                value.to_string()
+            } else {
+                format!("'{}'", value.replace('\'', "'\\''"))
+            }
+        }
+    }
+}
+
+/// Lists a command's placeholders in order of first use; errors on malformed ones.
+pub fn parse_parameters(command: &str) -> Result<Vec<TemplateParameter>, String> {
+    parse_template(command).map(|(_, parameters)| parameters)
+}
+
+/// Substitutes validated values into the command, falling back to defaults.
+pub fn render(command: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let (segments, parameters) = parse_template(command)?;
+
+    if let Some(unknown) = values.keys().find(|key| !parameters.iter().any(|p| &p.name == *key)) {
+        return Err(format!("Unknown parameter '{}'", unknown));
+    }
+    let missing: Vec<&str> = parameters.iter()
+        .filter(|p| p.required && !values.contains_key(&p.name))
+        .map(|p| p.name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing values for: {}", missing.join(", ")));
+    }
+
+    let mut resolved = HashMap::new();
+    for parameter in &parameters {
+        let value = values.get(&parameter.name).or(parameter.default.as_ref()).cloned().unwrap_or_default();
+        resolved.insert(parameter.name.as_str(), validate_value(&parameter.name, &parameter.kind, &value)?);
+    }
+
+    let mut rendered = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => rendered.push_str(text),
+            Segment::Placeholder { name, quote } => rendered.push_str(&shell_quote(&resolved[name.as_str()], quote)),
+        }
+    }
+
+    Ok(rendered)
+}
+
+pub fn template_command(conn: &Connection, id: i32) -> Result<String, String> {
+    conn.query_row(
+        "SELECT command FROM command_templates WHERE id = ? AND deleted_at IS NULL",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Command template {} not found", id))
+}
+
+// Command template parameter commands
+#[tauri::command]
+pub fn get_template_parameters(id: i32) -> Result<Vec<TemplateParameter>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    parse_parameters(&template_command(conn, id)?)
+}
+
+#[tauri::command]
+pub fn render_command_template(id: i32, values: HashMap<String, String>) -> Result<String, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    render(&template_command(conn, id)?, &values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AWKWARD: &str = "it's \"$HOME\" `id` \\ ; rm -rf *";
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn shell_quote_leaves_safe_words_bare() {
+        assert_eq!(shell_quote("feature/x-1.2", Quote::None), "feature/x-1.2");
+        assert_eq!(shell_quote("", Quote::None), "''");
+        assert_eq!(shell_quote("a b", Quote::None), "'a b'");
+        assert_eq!(shell_quote("it's", Quote::None), "'it'\\''s'");
+    }
+
+    #[test]
+    fn shell_quote_escapes_inside_quotes() {
+        assert_eq!(shell_quote("it's", Quote::Single), "it'\\''s");
+        assert_eq!(shell_quote("a \"$b\" `c` \\", Quote::Double), "a \\\"\\$b\\\" \\`c\\` \\\\");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rendered_values_reach_the_shell_intact() {
+        for template in ["printf %s {{v}}", "printf %s '{{v}}'", "printf %s \"{{v}}\""] {
+            let command = render(template, &values(&[("v", AWKWARD)])).unwrap();
+            let output = std::process::Command::new("sh").args(["-c", &command]).output().unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), AWKWARD, "{}", command);
+        }
+    }
+
+    #[test]
+    fn placeholders_are_typed_and_defaulted() {
+        let parameters = parse_parameters("ssh {{host}} -p {{port:int=22}} {{verbose:bool=no}}").unwrap();
+        let kinds: Vec<(&str, &str, bool)> = parameters.iter().map(|p| (p.name.as_str(), p.kind.as_str(), p.required)).collect();
+        assert_eq!(kinds, vec![("host", "string", true), ("port", "int", false), ("verbose", "bool", false)]);
+
+        assert_eq!(render("ssh {{host}} -p {{port:int=22}}", &values(&[("host", "box")])).unwrap(), "ssh box -p 22");
+        assert!(render("ssh {{host}} -p {{port:int=22}}", &values(&[("host", "box"), ("port", "x")])).is_err());
+        assert!(render("ssh {{host}}", &HashMap::new()).is_err());
+        assert!(parse_parameters("x {{p:int=abc}}").is_err());
+    }
+
+    #[test]
+    fn unknown_kinds_and_foreign_braces_are_kept_usable() {
+        let parameters = parse_parameters("helm --set {{image:tag}} {{.Values.x}}").unwrap();
+        assert_eq!(parameters.len(), 1);
+        assert_eq!((parameters[0].name.as_str(), parameters[0].kind.as_str()), ("image", "string"));
+        assert_eq!(render("echo {{image:tag}} {{.State}}", &values(&[("image", "v1")])).unwrap(), "echo v1 {{.State}}");
+    }
+}
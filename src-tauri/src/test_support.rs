@@ -0,0 +1,17 @@
+use rusqlite::Connection;
+
+/// An in-memory database with the portfolio table and the ledger and price tables that hang off it.
+pub fn db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute(
+        "CREATE TABLE portfolio (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, symbol TEXT NOT NULL, shares REAL NOT NULL, avg_price REAL NOT NULL,
+            current_price REAL DEFAULT 0, total_value REAL DEFAULT 0, gain_loss REAL DEFAULT 0, gain_loss_percent REAL DEFAULT 0,
+            notes TEXT, closed_at TEXT, deleted_at TEXT
+        )",
+        [],
+    ).unwrap();
+    crate::lots::init_tables(&conn).unwrap();
+    crate::prices::init_tables(&conn).unwrap();
+    conn
+}
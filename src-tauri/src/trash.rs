@@ -0,0 +1,230 @@
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::{add_column_if_missing, DB};
+
+/// Entities that can be moved to the trash: `(kind, table, title expression, label)`.
+const TRASHABLE: [(&str, &str, &str, &str); 5] = [
+    ("note", "notes", "title", "Note"),
+    ("invoice", "invoices", "invoice_number || ' - ' || client_name", "Invoice"),
+    ("portfolio", "portfolio", "symbol", "Portfolio item"),
+    ("snippet", "code_snippets", "title", "Code snippet"),
+    ("command", "command_templates", "title", "Command template"),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashItem {
+    kind: String,
+    id: i32,
+    title: String,
+    deleted_at: String,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    for (_, table, _, _) in TRASHABLE {
+        add_column_if_missing(conn, table, "deleted_at", "TEXT")?;
+    }
+
+    Ok(())
+}
+
+fn lookup(kind: &str) -> Result<(&'static str, &'static str, &'static str, &'static str), String> {
+    TRASHABLE.iter()
+        .find(|(name, ..)| *name == kind)
+        .copied()
+        .ok_or_else(|| format!("Unknown trash kind '{}'", kind))
+}
+
+/// Soft-deletes a row; it stays restorable until the trash is emptied or it expires.
+pub fn move_to_trash(conn: &Connection, kind: &str, id: i32) -> Result<(), String> {
+    let (_, table, _, label) = lookup(kind)?;
+    let now = chrono::Utc::now().to_rfc3339();
+
+    let updated = conn.execute(
+        &format!("UPDATE {} SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL", table),
+        params![now, id],
+    ).map_err(|e| format!("Database error: {}", e))?;
+    if updated == 0 {
+        return Err(format!("{} {} not found", label, id));
+    }
+
+    Ok(())
+}
+
+/// Trashes several rows at once; if any id is missing nothing is moved.
+pub fn move_many_to_trash(conn: &Connection, kind: &str, ids: &[i32]) -> Result<usize, String> {
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.dedup();
+    for id in &ids {
+        move_to_trash(&tx, kind, *id)?;
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    Ok(ids.len())
+}
+
+/// Permanently deletes trashed rows along with the data that hangs off them.
+pub fn purge(conn: &Connection, kind: &str, ids: &[i32]) -> Result<usize, String> {
+    let (_, table, _, _) = lookup(kind)?;
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut purged = 0;
+    for id in ids {
+        match kind {
+            "note" => {
+                for sql in [
+                    "DELETE FROM note_revisions WHERE note_id = ?",
+                    "DELETE FROM note_links WHERE source_note_id = ?",
+                    "DELETE FROM note_attachments WHERE note_id = ?",
+                ] {
+                    tx.execute(sql, params![id])
+                        .map_err(|e| format!("Database error: {}", e))?;
+                }
+            }
+            "portfolio" => {
+                // A purged position takes the symbol's ledger with it
+                for sql in [
+                    "DELETE FROM imported_rows WHERE transaction_id IN (SELECT id FROM portfolio_transactions WHERE symbol = (SELECT symbol FROM portfolio WHERE id = ?))",
+                    "DELETE FROM realized_gains WHERE symbol = (SELECT symbol FROM portfolio WHERE id = ?)",
+                    "DELETE FROM tax_lots WHERE symbol = (SELECT symbol FROM portfolio WHERE id = ?)",
+                    "DELETE FROM portfolio_transactions WHERE symbol = (SELECT symbol FROM portfolio WHERE id = ?)",
+                ] {
+                    tx.execute(sql, params![id])
+                        .map_err(|e| format!("Database error: {}", e))?;
+                }
+                let symbol: Option<String> = tx.query_row("SELECT symbol FROM portfolio WHERE id = ?", params![id], |row| row.get(0))
+                    .optional()
+                    .map_err(|e| format!("Database error: {}", e))?;
+                if let Some(symbol) = symbol {
+                    crate::alerts::clear_symbol_alerts(&tx, &symbol)?;
+                }
+            }
+            _ => {}
+        }
+        crate::tags::clear_entity_tags(&tx, kind, *id)?;
+        // Wiki links to the purged row dangle until something else takes its title
+        tx.execute("UPDATE note_links SET target_id = NULL WHERE target_kind = ? AND target_id = ?", params![kind, id])
+            .map_err(|e| format!("Database error: {}", e))?;
+        purged += tx.execute(&format!("DELETE FROM {} WHERE id = ? AND deleted_at IS NOT NULL", table), params![id])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+    Ok(purged)
+}
+
+fn trashed_ids(conn: &Connection, table: &str, older_than: Option<&str>) -> Result<Vec<i32>, String> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id FROM {} WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < ?1)",
+        table
+    )).map_err(|e| format!("Database error: {}", e))?;
+
+    let ids = stmt.query_map(params![older_than], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let mut result = Vec::new();
+    for id in ids {
+        result.push(id.map_err(|e| format!("Database error: {}", e))?);
+    }
+
+    Ok(result)
+}
+
+/// Purges everything trashed longer ago than the `trash_retention_days` setting (0 keeps it forever).
+pub fn purge_expired(conn: &Connection) -> Result<usize, String> {
+    let days: i64 = conn.query_row("SELECT COALESCE(trash_retention_days, 30) FROM settings LIMIT 1", [], |row| row.get(0))
+        .unwrap_or(30);
+    if days <= 0 {
+        return Ok(0);
+    }
+
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+    let mut purged = 0;
+    for (kind, table, _, _) in TRASHABLE {
+        let ids = trashed_ids(conn, table, Some(&cutoff))?;
+        purged += purge(conn, kind, &ids)?;
+    }
+
+    Ok(purged)
+}
+
+// Trash commands
+#[tauri::command]
+pub fn list_trash(kind: Option<String>) -> Result<Vec<TrashItem>, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    purge_expired(conn)?;
+
+    let kinds = match &kind {
+        Some(kind) => vec![lookup(kind)?],
+        None => TRASHABLE.to_vec(),
+    };
+
+    let mut result = Vec::new();
+    for (kind, table, title, _) in kinds {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, COALESCE({}, ''), deleted_at FROM {} WHERE deleted_at IS NOT NULL",
+            title, table
+        )).map_err(|e| format!("Database error: {}", e))?;
+
+        let items = stmt.query_map([], |row| {
+            Ok(TrashItem {
+                kind: kind.to_string(),
+                id: row.get(0)?,
+                title: row.get(1)?,
+                deleted_at: row.get(2)?,
+            })
+        }).map_err(|e| format!("Database error: {}", e))?;
+
+        for item in items {
+            result.push(item.map_err(|e| format!("Database error: {}", e))?);
+        }
+    }
+
+    result.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn restore_from_trash(kind: String, id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let (_, table, _, label) = lookup(&kind)?;
+    let updated = conn.execute(&format!("UPDATE {} SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL", table), params![id])
+        .map_err(|e| format!("Database error: {}", e))?;
+    if updated == 0 {
+        return Err(format!("{} {} is not in the trash", label, id));
+    }
+    crate::links::resolve_dangling(conn)?;
+    if kind == "snippet" {
+        crate::snippet_sync::record_change(conn);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn empty_trash(kind: Option<String>) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let kinds = match &kind {
+        Some(kind) => vec![lookup(kind)?],
+        None => TRASHABLE.to_vec(),
+    };
+
+    let mut purged = 0;
+    for (kind, table, _, _) in kinds {
+        let ids = trashed_ids(conn, table, None)?;
+        purged += purge(conn, kind, &ids)?;
+    }
+
+    Ok(purged)
+}
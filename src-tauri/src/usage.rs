@@ -0,0 +1,69 @@
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+
+use crate::{add_column_if_missing, DB};
+
+/// Days for a use to count half as much towards a template's frecency.
+const HALF_LIFE_DAYS: f64 = 14.0;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandUsage {
+    id: i32,
+    use_count: i64,
+    last_used_at: Option<String>,
+    frecency: f64,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    add_column_if_missing(conn, "command_templates", "use_count", "INTEGER DEFAULT 0")?;
+    add_column_if_missing(conn, "command_templates", "last_used_at", "TEXT")?;
+    // Stored as log2(score) + now / half-life, which orders templates by their current decayed
+    // score without rewriting every row as time passes
+    add_column_if_missing(conn, "command_templates", "frecency", "REAL")?;
+
+    Ok(())
+}
+
+fn half_lives_since_epoch(now: chrono::DateTime<chrono::Utc>) -> f64 {
+    now.timestamp() as f64 / 86_400.0 / HALF_LIFE_DAYS
+}
+
+/// A template's frecency score right now: each use counts 1, halving every `HALF_LIFE_DAYS`.
+pub fn current_score(key: Option<f64>) -> f64 {
+    key.map(|key| (key - half_lives_since_epoch(chrono::Utc::now())).exp2())
+        .unwrap_or(0.0)
+}
+
+/// Counts one use of a template, decaying its earlier uses before adding this one.
+pub fn record_use(conn: &Connection, id: i32) -> Result<CommandUsage, String> {
+    let key: Option<f64> = conn.query_row(
+        "SELECT frecency FROM command_templates WHERE id = ? AND deleted_at IS NULL",
+        params![id],
+        |row| row.get(0),
+    ).map_err(|_| format!("Command template {} not found", id))?;
+
+    let now = chrono::Utc::now();
+    let offset = half_lives_since_epoch(now);
+    let score = key.map(|key| (key - offset).exp2()).unwrap_or(0.0) + 1.0;
+
+    conn.execute(
+        "UPDATE command_templates SET use_count = COALESCE(use_count, 0) + 1, last_used_at = ?, frecency = ? WHERE id = ?",
+        params![now.to_rfc3339(), score.log2() + offset, id],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    conn.query_row(
+        "SELECT COALESCE(use_count, 0), last_used_at FROM command_templates WHERE id = ?",
+        params![id],
+        |row| Ok(CommandUsage { id, use_count: row.get(0)?, last_used_at: row.get(1)?, frecency: score }),
+    ).map_err(|e| format!("Database error: {}", e))
+}
+
+// Command usage commands
+/// Called when a template is copied; runs are counted by the runner itself.
+#[tauri::command]
+pub fn record_command_usage(id: i32) -> Result<CommandUsage, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    record_use(conn, id)
+}
@@ -0,0 +1,330 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use lazy_static::lazy_static;
+use rusqlite::{params, Connection, Result as SqliteResult};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use zeroize::Zeroizing;
+
+use crate::{add_column_if_missing, DB};
+
+const VERIFIER: &[u8] = b"simplelife-vault";
+
+type VaultKey = Zeroizing<[u8; 32]>;
+
+struct VaultSession {
+    key: VaultKey,
+    last_used: Instant,
+    idle_timeout: Duration,
+}
+
+lazy_static! {
+    static ref SESSION: Mutex<Option<VaultSession>> = Mutex::new(None);
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultStatus {
+    configured: bool,
+    unlocked: bool,
+    /// Seconds until the vault locks itself if left idle.
+    locks_in: Option<u64>,
+}
+
+pub fn init_tables(conn: &Connection) -> SqliteResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS vault (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            salt BLOB NOT NULL,
+            memory_cost INTEGER NOT NULL,
+            time_cost INTEGER NOT NULL,
+            parallelism INTEGER NOT NULL,
+            verifier BLOB NOT NULL,
+            verifier_nonce BLOB NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "notes", "encrypted", "BOOLEAN DEFAULT 0")?;
+    add_column_if_missing(conn, "notes", "ciphertext", "BLOB")?;
+    add_column_if_missing(conn, "notes", "nonce", "BLOB")?;
+
+    Ok(())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], memory_cost: u32, time_cost: u32, parallelism: u32) -> Result<VaultKey, String> {
+    let params = Params::new(memory_cost, time_cost, parallelism, Some(32))
+        .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn seal(key: &VaultKey, plaintext: &[u8], aad: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher.encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok((ciphertext, nonce.to_vec()))
+}
+
+fn open(key: &VaultKey, ciphertext: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+    if nonce.len() != 24 {
+        return Err("Corrupt encrypted note".to_string());
+    }
+    let cipher = XChaCha20Poly1305::new(key.as_ref().into());
+    cipher.decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| "Decryption failed".to_string())
+}
+
+// Binding the note id into the ciphertext stops one note's content being swapped into another.
+fn note_aad(note_id: i32) -> Vec<u8> {
+    format!("note:{}", note_id).into_bytes()
+}
+
+/// The unlocked key, or `None` when locked. Each use pushes the idle deadline back.
+pub fn session_key() -> Option<VaultKey> {
+    let mut session = SESSION.lock().unwrap();
+    match session.as_mut() {
+        Some(active) if active.last_used.elapsed() < active.idle_timeout => {
+            active.last_used = Instant::now();
+            Some(active.key.clone())
+        }
+        Some(_) => {
+            *session = None;
+            None
+        }
+        None => None,
+    }
+}
+
+fn require_key() -> Result<VaultKey, String> {
+    session_key().ok_or_else(|| "Unlock the vault to access encrypted notes".to_string())
+}
+
+pub fn is_encrypted(conn: &Connection, note_id: i32) -> bool {
+    conn.query_row("SELECT COALESCE(encrypted, 0) FROM notes WHERE id = ?", params![note_id], |row| row.get(0))
+        .unwrap_or(false)
+}
+
+/// Encrypts `content` into the note's ciphertext and blanks the plaintext column, so
+/// full-text search and global search only ever see the title.
+pub fn write_encrypted_content(conn: &Connection, note_id: i32, content: &str) -> Result<(), String> {
+    let key = require_key()?;
+    let (ciphertext, nonce) = seal(&key, content.as_bytes(), &note_aad(note_id))?;
+    conn.execute(
+        "UPDATE notes SET encrypted = 1, ciphertext = ?, nonce = ?, content = '' WHERE id = ?",
+        params![ciphertext, nonce, note_id],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    // Plaintext history and link context would leak what was just encrypted
+    for sql in [
+        "DELETE FROM note_revisions WHERE note_id = ?",
+        "DELETE FROM note_links WHERE source_note_id = ?",
+    ] {
+        conn.execute(sql, params![note_id])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Decrypted content with the given key; `None` if it doesn't open.
+pub fn decrypt_with(key: &VaultKey, note_id: i32, ciphertext: &[u8], nonce: &[u8]) -> Option<String> {
+    open(key, ciphertext, nonce, &note_aad(note_id)).ok()
+        .and_then(|plaintext| String::from_utf8(plaintext).ok())
+}
+
+/// The note's plaintext, decrypting it when it is encrypted.
+pub fn read_content(conn: &Connection, note_id: i32) -> Result<String, String> {
+    let (content, encrypted, ciphertext, nonce): (String, bool, Option<Vec<u8>>, Option<Vec<u8>>) = conn.query_row(
+        "SELECT COALESCE(content, ''), COALESCE(encrypted, 0), ciphertext, nonce FROM notes WHERE id = ?",
+        params![note_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).map_err(|_| format!("Note {} not found", note_id))?;
+
+    if !encrypted {
+        return Ok(content);
+    }
+    let key = require_key()?;
+    let plaintext = open(&key, &ciphertext.unwrap_or_default(), &nonce.unwrap_or_default(), &note_aad(note_id))?;
+    String::from_utf8(plaintext).map_err(|_| "Corrupt encrypted note".to_string())
+}
+
+fn unlock_with(conn: &Connection, passphrase: &str) -> Result<VaultKey, String> {
+    let (salt, memory_cost, time_cost, parallelism, verifier, verifier_nonce): (Vec<u8>, u32, u32, u32, Vec<u8>, Vec<u8>) = conn.query_row(
+        "SELECT salt, memory_cost, time_cost, parallelism, verifier, verifier_nonce FROM vault WHERE id = 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
+    ).map_err(|_| "No vault passphrase has been set".to_string())?;
+
+    let key = derive_key(passphrase, &salt, memory_cost, time_cost, parallelism)?;
+    match open(&key, &verifier, &verifier_nonce, b"vault") {
+        Ok(plaintext) if plaintext == VERIFIER => Ok(key),
+        _ => Err("Wrong passphrase".to_string()),
+    }
+}
+
+fn start_session(conn: &Connection, key: VaultKey) {
+    let minutes: i64 = conn.query_row("SELECT COALESCE(vault_lock_minutes, 15) FROM settings LIMIT 1", [], |row| row.get(0))
+        .unwrap_or(15);
+    *SESSION.lock().unwrap() = Some(VaultSession {
+        key,
+        last_used: Instant::now(),
+        idle_timeout: Duration::from_secs(minutes.max(1) as u64 * 60),
+    });
+}
+
+fn store_vault(conn: &Connection, passphrase: &str) -> Result<VaultKey, String> {
+    if passphrase.chars().count() < 8 {
+        return Err("Passphrase must be at least 8 characters".to_string());
+    }
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let defaults = Params::default();
+    let (memory_cost, time_cost, parallelism) = (defaults.m_cost(), defaults.t_cost(), defaults.p_cost());
+
+    let key = derive_key(passphrase, &salt, memory_cost, time_cost, parallelism)?;
+    let (verifier, verifier_nonce) = seal(&key, VERIFIER, b"vault")?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT OR REPLACE INTO vault (id, salt, memory_cost, time_cost, parallelism, verifier, verifier_nonce, created_at) VALUES (1, ?, ?, ?, ?, ?, ?, ?)",
+        params![salt.to_vec(), memory_cost, time_cost, parallelism, verifier, verifier_nonce, now],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(key)
+}
+
+/// Background check that drops the key once the session has sat idle, emitting `vault-locked`.
+pub fn spawn_idle_lock(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(30));
+        let expired = {
+            let mut session = SESSION.lock().unwrap();
+            let expired = matches!(session.as_ref(), Some(active) if active.last_used.elapsed() >= active.idle_timeout);
+            if expired {
+                *session = None;
+            }
+            expired
+        };
+        if expired {
+            if let Err(e) = app.emit("vault-locked", ()) {
+                eprintln!("Failed to emit vault lock: {}", e);
+            }
+        }
+    });
+}
+
+// Vault commands
+#[tauri::command]
+pub fn vault_status() -> Result<VaultStatus, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let configured: bool = conn.query_row("SELECT COUNT(*) > 0 FROM vault", [], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+    let session = SESSION.lock().unwrap();
+    let locks_in = session.as_ref()
+        .and_then(|active| active.idle_timeout.checked_sub(active.last_used.elapsed()))
+        .map(|remaining| remaining.as_secs());
+
+    Ok(VaultStatus { configured, unlocked: locks_in.is_some(), locks_in })
+}
+
+#[tauri::command]
+pub fn setup_vault(passphrase: String) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let configured: bool = conn.query_row("SELECT COUNT(*) > 0 FROM vault", [], |row| row.get(0))
+        .map_err(|e| format!("Database error: {}", e))?;
+    if configured {
+        return Err("A vault passphrase is already set".to_string());
+    }
+
+    let key = store_vault(conn, &passphrase)?;
+    start_session(conn, key);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unlock_vault(passphrase: String) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let key = unlock_with(conn, &passphrase)?;
+    start_session(conn, key);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn lock_vault() -> Result<(), String> {
+    *SESSION.lock().unwrap() = None;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn change_vault_passphrase(current: String, new_passphrase: String) -> Result<usize, String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let old_key = unlock_with(conn, &current)?;
+
+    let mut stmt = conn.prepare("SELECT id, ciphertext, nonce FROM notes WHERE encrypted = 1")
+        .map_err(|e| format!("Database error: {}", e))?;
+    let rows: Vec<(i32, Vec<u8>, Vec<u8>)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| format!("Database error: {}", e))?
+        .collect::<SqliteResult<_>>()
+        .map_err(|e| format!("Database error: {}", e))?;
+
+    let tx = conn.unchecked_transaction()
+        .map_err(|e| format!("Database error: {}", e))?;
+    let new_key = store_vault(&tx, &new_passphrase)?;
+    for (id, ciphertext, nonce) in &rows {
+        let plaintext = Zeroizing::new(open(&old_key, ciphertext, nonce, &note_aad(*id))?);
+        let (ciphertext, nonce) = seal(&new_key, &plaintext, &note_aad(*id))?;
+        tx.execute("UPDATE notes SET ciphertext = ?, nonce = ? WHERE id = ?", params![ciphertext, nonce, id])
+            .map_err(|e| format!("Database error: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Database error: {}", e))?;
+
+    start_session(conn, new_key);
+    Ok(rows.len())
+}
+
+#[tauri::command]
+pub fn encrypt_note(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if is_encrypted(conn, id) {
+        return Err(format!("Note {} is already encrypted", id));
+    }
+    let content = read_content(conn, id)?;
+    write_encrypted_content(conn, id, &content)
+}
+
+#[tauri::command]
+pub fn decrypt_note(id: i32) -> Result<(), String> {
+    let db_guard = DB.lock().unwrap();
+    let conn = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    if !is_encrypted(conn, id) {
+        return Err(format!("Note {} is not encrypted", id));
+    }
+    let content = read_content(conn, id)?;
+    conn.execute(
+        "UPDATE notes SET encrypted = 0, ciphertext = NULL, nonce = NULL, content = ? WHERE id = ?",
+        params![content, id],
+    ).map_err(|e| format!("Database error: {}", e))?;
+
+    crate::revisions::snapshot_note(conn, id)?;
+    crate::links::sync_note_links(conn, id, &content)
+}